@@ -0,0 +1,191 @@
+//! Compares the median-split and SAH [`BvhNode`] builders on the book 2 final scene.
+//!
+//! Run with `cargo bench --bench bvh`.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use raytracer::{
+    bvh::{BvhNode, SahOptions},
+    camera::Camera,
+    hittable::{Hittable, HittableList, Transform},
+    material::*,
+    math::{Color, Mat4, Point3, Vec3},
+    primitives::*,
+    sampler::{IndependentSampler, Sampler},
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const BUILD_RUNS: u32 = 10;
+const IMAGE_SIZE: usize = 400;
+
+type Builder = fn(&HittableList) -> BvhNode;
+
+fn main() {
+    let builders: [(&str, Builder); 2] = [
+        ("median", |list| BvhNode::from_list(list, 0.0, 1.0)),
+        ("sah", |list| {
+            BvhNode::from_list_sah(list, 0.0, 1.0, SahOptions::default())
+        }),
+    ];
+
+    let camera = Camera::new(
+        Point3::new(478.0, 278.0, -600.0),
+        Point3::new(278.0, 278.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        10.0,
+        0.0,
+        1.0,
+    );
+
+    println!(
+        "{:<8} {:>12} {:>12} {:>14}",
+        "builder", "build (ms)", "trace (ms)", "Mrays/s"
+    );
+
+    for (name, builder) in builders.iter() {
+        let mut build_time = Duration::default();
+        let mut scene = None;
+        for _ in 0..BUILD_RUNS {
+            let start = Instant::now();
+            scene = Some(final_scene(*builder));
+            build_time += start.elapsed();
+        }
+        let scene = scene.unwrap();
+
+        let start = Instant::now();
+        let mut hits = 0usize;
+        let mut sampler = IndependentSampler::new(0);
+        for j in 0..IMAGE_SIZE {
+            for i in 0..IMAGE_SIZE {
+                let u = (i as f64 + 0.5) / IMAGE_SIZE as f64;
+                let v = (j as f64 + 0.5) / IMAGE_SIZE as f64;
+                sampler.start_pixel_sample(i, j, 0, 1);
+                if scene
+                    .hit(&camera.get_ray(u, v, &mut sampler), 0.001, f64::INFINITY)
+                    .is_some()
+                {
+                    hits += 1;
+                }
+            }
+        }
+        let trace_time = start.elapsed();
+        let rays = (IMAGE_SIZE * IMAGE_SIZE) as f64;
+
+        println!(
+            "{:<8} {:>12.2} {:>12.2} {:>14.2}   ({} hits)",
+            name,
+            build_time.as_secs_f64() * 1000.0 / BUILD_RUNS as f64,
+            trace_time.as_secs_f64() * 1000.0,
+            rays / trace_time.as_secs_f64() / 1.0e6,
+            hits
+        );
+    }
+}
+
+/// Geometry of the book 2 final scene, with every BVH built by `builder`.
+///
+/// The random box heights and sphere positions come from a fixed seed so both builders see the
+/// same scene.
+fn final_scene(builder: Builder) -> BvhNode {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let mat_ground: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.48, 0.83, 0.53)));
+    let mut boxes_0 = HittableList::new();
+    let boxes_per_side = 20;
+    for i in 0..boxes_per_side {
+        for j in 0..boxes_per_side {
+            let w = 100.0;
+            let x0 = -1000.0 + i as f64 * w;
+            let z0 = -1000.0 + j as f64 * w;
+            let y0 = 0.0;
+            let x1 = x0 + w;
+            let y1 = rng.gen_range(1.0, 101.0);
+            let z1 = z0 + w;
+
+            boxes_0.add(AaBox::new(
+                Point3::new(x0, y0, z0),
+                Point3::new(x1, y1, z1),
+                Some(mat_ground.clone()),
+            ));
+        }
+    }
+
+    let mut scene_objects = HittableList::new();
+    scene_objects.add(builder(&boxes_0));
+
+    let mat_light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(7.0, 7.0, 7.0)));
+    scene_objects.add(XzPlane::new(
+        123.0,
+        423.0,
+        147.0,
+        412.0,
+        554.0,
+        Some(mat_light),
+    ));
+
+    let center_0 = Point3::new(400.0, 400.0, 200.0);
+    let center_1 = center_0 + Vec3::new(30.0, 0.0, 0.0);
+    let mat_moving: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.1)));
+    scene_objects.add(MovingSphere::new(
+        center_0,
+        center_1,
+        50.0,
+        0.0,
+        1.0,
+        Some(mat_moving),
+    ));
+
+    let mat_glass: Arc<dyn Material> = Arc::new(Dielectric::new(1.5));
+    scene_objects.add(Sphere::new(
+        Point3::new(260.0, 150.0, 45.0),
+        50.0,
+        Some(mat_glass.clone()),
+    ));
+    scene_objects.add(Sphere::new(
+        Point3::new(0.0, 150.0, 145.0),
+        50.0,
+        Some(Arc::new(Metal::new(Color::new(0.8, 0.8, 0.9), 1.0))),
+    ));
+
+    let boundary: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point3::new(360.0, 150.0, 145.0),
+        70.0,
+        Some(mat_glass.clone()),
+    ));
+    scene_objects.add_shared(boundary.clone());
+    scene_objects.add(ConstantMedium::from_color(
+        boundary,
+        0.2,
+        Color::new(0.2, 0.4, 0.9),
+    ));
+
+    let mat_white: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    scene_objects.add(Sphere::new(
+        Point3::new(400.0, 200.0, 400.0),
+        100.0,
+        Some(mat_white.clone()),
+    ));
+    scene_objects.add(Sphere::new(
+        Point3::new(220.0, 280.0, 300.0),
+        80.0,
+        Some(mat_white.clone()),
+    ));
+
+    let mut boxes_1 = HittableList::new();
+    for _ in 0..1000 {
+        let center = Point3::new(
+            rng.gen_range(0.0, 165.0),
+            rng.gen_range(0.0, 165.0),
+            rng.gen_range(0.0, 165.0),
+        );
+        boxes_1.add(Sphere::new(center, 10.0, Some(mat_white.clone())));
+    }
+    let spheres = Transform::new(Arc::new(builder(&boxes_1)), Mat4::rotation_y(15.0))
+        .translate(Vec3::new(-100.0, 270.0, 395.0));
+    scene_objects.add(spheres);
+
+    builder(&scene_objects)
+}
@@ -1,13 +1,16 @@
 use clap::{App, Arg, ArgMatches};
-use image;
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use raytracer::{
     camera::Camera,
+    film::{filter_by_name, tone_map_by_name, FILTER_NAMES, TONE_MAP_NAMES},
     hittable::HittableList,
+    integrator::{integrator_by_name, INTEGRATOR_NAMES},
+    light::Sky,
     material::*,
     math::{Color, Point3, Vec3},
     primitives::Sphere,
-    scene::Scene,
+    sampler::{sampler_by_name, SAMPLER_NAMES},
+    scene::{AdaptiveOptions, Scene},
 };
 use std::sync::Arc;
 
@@ -31,6 +34,17 @@ fn main() {
     // Parse filename
     let filename = matches.value_of("output").unwrap();
 
+    // Parse output args
+    let white = matches.value_of("white").map(|w| w.parse().unwrap());
+    let tone_map = tone_map_by_name(matches.value_of("tonemap").unwrap(), white).unwrap();
+    let integrator = integrator_by_name(matches.value_of("integrator").unwrap()).unwrap();
+    let seed: u64 = matches.value_of("seed").unwrap().parse().unwrap();
+    let sampler = sampler_by_name(matches.value_of("sampler").unwrap(), seed).unwrap();
+    let filter_radius = matches.value_of("filterradius").map(|r| r.parse().unwrap());
+    let filter = filter_by_name(matches.value_of("filter").unwrap(), filter_radius)
+        .expect("filter radius must be positive");
+    let exposure: f32 = matches.value_of("exposure").unwrap().parse().unwrap();
+
     // Camera
     let look_from = Point3::new(13.0, 2.0, 3.0);
     let look_at = Point3::new(0.0, 0.0, 0.0);
@@ -49,7 +63,7 @@ fn main() {
     );
 
     // Init scene
-    let scene = random_scene(camera);
+    let mut scene = random_scene(camera);
 
     println!(
         "Rendering scene to {}x{} image ({} pixels) with {} bounces/ray and {} samples/pixel",
@@ -61,15 +75,26 @@ fn main() {
     );
 
     // Render
-    let image = scene.render(image_width, image_height, samples_per_pixel, max_depth);
-    image::save_buffer(
-        filename,
-        &image,
-        image_width as u32,
-        image_height as u32,
-        image::ColorType::Rgb8,
-    )
-    .unwrap();
+    scene.set_integrator(integrator);
+    scene.set_sampler(sampler);
+    scene.set_filter(filter);
+    let film = if matches.is_present("adaptive") {
+        let options = AdaptiveOptions {
+            min_samples: matches.value_of("minsamples").unwrap().parse().unwrap(),
+            max_samples: samples_per_pixel,
+            threshold: matches.value_of("threshold").unwrap().parse().unwrap(),
+            ..AdaptiveOptions::default()
+        };
+        scene.render_adaptive(image_width, image_height, options, max_depth)
+    } else {
+        scene.render(image_width, image_height, samples_per_pixel, max_depth)
+    };
+    film.save_with(filename, tone_map.as_ref(), exposure)
+        .unwrap();
+
+    if let Some(heatmap) = matches.value_of("heatmap") {
+        film.sample_heatmap().save(heatmap).unwrap();
+    }
 }
 
 fn random_scene(camera: Camera) -> Scene {
@@ -79,10 +104,11 @@ fn random_scene(camera: Camera) -> Scene {
     scene_objects.add(Sphere::new(
         Point3::new(0.0, -1000.0, 0.0),
         1000.0,
-        mat_ground.clone(),
+        Some(mat_ground.clone()),
     ));
 
-    let mut rng = thread_rng();
+    // a fixed seed keeps the generated scene the same between runs
+    let mut rng = StdRng::seed_from_u64(0);
 
     for a in -11..11 {
         for b in -11..11 {
@@ -97,18 +123,18 @@ fn random_scene(camera: Camera) -> Scene {
                 let material: Arc<dyn Material>;
                 if choose_mat < 0.8 {
                     // diffuse
-                    let albedo = Vec3::random() * Vec3::random();
+                    let albedo = Vec3::random_with(&mut rng) * Vec3::random_with(&mut rng);
                     material = Arc::new(Lambertian::new(albedo));
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = Vec3::random_in_range(0.5, 1.0);
-                    let fuzz: f64 = rng.gen_range(0.0, 0.5);
-                    material = Arc::new(Metal::new(albedo, fuzz));
+                    let albedo = Vec3::random_in_range_with(&mut rng, 0.5, 1.0);
+                    let roughness: f64 = rng.gen_range(0.0, 0.5);
+                    material = Arc::new(Metal::new(albedo, roughness));
                 } else {
                     // glass
                     material = Arc::new(Dielectric::new(1.5));
                 }
-                scene_objects.add(Sphere::new(center, 0.2, material));
+                scene_objects.add(Sphere::new(center, 0.2, Some(material)));
             }
         }
     }
@@ -117,15 +143,30 @@ fn random_scene(camera: Camera) -> Scene {
     let lambert: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)));
     let metal: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0));
 
-    scene_objects.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, glass.clone()));
+    scene_objects.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Some(glass.clone()),
+    ));
     scene_objects.add(Sphere::new(
         Point3::new(-4.0, 1.0, 0.0),
         1.0,
-        lambert.clone(),
+        Some(lambert.clone()),
+    ));
+    scene_objects.add(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        Some(metal.clone()),
     ));
-    scene_objects.add(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, metal.clone()));
 
-    Scene::new(scene_objects, Color::new(0.7, 0.8, 1.0), camera)
+    let mut scene = Scene::new(scene_objects, Color::default(), camera);
+    // afternoon sun off to the side, low enough to cast long shadows
+    scene.set_environment(Box::new(Sky::new(
+        Vec3::new(0.5, 0.7, 1.0),
+        Sky::DEFAULT_TURBIDITY,
+        Color::new(0.5, 0.5, 0.5),
+    )));
+    scene
 }
 
 fn match_args() -> ArgMatches<'static> {
@@ -184,7 +225,35 @@ fn match_args() -> ArgMatches<'static> {
                 .value_name("SAMPLES")
                 .takes_value(true)
                 .default_value("500")
-                .help("Sets number of samples per pixel"),
+                .help("Sets number of samples per pixel (the most per pixel with --adaptive)"),
+        )
+        .arg(
+            Arg::with_name("adaptive")
+                .long("adaptive")
+                .help("Stops sampling each pixel once its estimated error is below --threshold"),
+        )
+        .arg(
+            Arg::with_name("minsamples")
+                .long("min-samples")
+                .value_name("SAMPLES")
+                .takes_value(true)
+                .default_value("16")
+                .help("Sets number of samples per pixel before adaptive sampling may stop"),
+        )
+        .arg(
+            Arg::with_name("threshold")
+                .long("threshold")
+                .value_name("ERROR")
+                .takes_value(true)
+                .default_value("0.05")
+                .help("Sets relative error at which adaptive sampling stops"),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .long("heatmap")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Saves an image of the number of samples taken in each pixel"),
         )
         .arg(
             Arg::with_name("bounces")
@@ -195,6 +264,75 @@ fn match_args() -> ArgMatches<'static> {
                 .default_value("50")
                 .help("Sets max bounces (depth) for each raycast"),
         )
+        .arg(
+            Arg::with_name("integrator")
+                .long("integrator")
+                .value_name("INTEGRATOR")
+                .takes_value(true)
+                .possible_values(INTEGRATOR_NAMES)
+                .default_value("path")
+                .help("Sets light transport algorithm (ao and normals are for previews and debugging)"),
+        )
+        .arg(
+            Arg::with_name("sampler")
+                .long("sampler")
+                .value_name("SAMPLER")
+                .takes_value(true)
+                .possible_values(SAMPLER_NAMES)
+                .default_value("independent")
+                .help("Sets sample generator for pixel, lens, time, light and BSDF samples"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .default_value("0")
+                .help("Sets random seed; renders with the same seed and options are identical"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .value_name("FILTER")
+                .takes_value(true)
+                .possible_values(FILTER_NAMES)
+                .default_value("box")
+                .help("Sets reconstruction filter samples are splatted onto pixels with"),
+        )
+        .arg(
+            Arg::with_name("filterradius")
+                .long("filter-radius")
+                .value_name("PIXELS")
+                .takes_value(true)
+                .help("Overrides the reconstruction filter's radius"),
+        )
+        .arg(
+            Arg::with_name("tonemap")
+                .short("t")
+                .long("tonemap")
+                .value_name("OPERATOR")
+                .takes_value(true)
+                .possible_values(TONE_MAP_NAMES)
+                .default_value("clamp")
+                .help("Sets tone mapping operator for 8-bit output"),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .short("e")
+                .long("exposure")
+                .value_name("EV")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .default_value("0")
+                .help("Sets exposure compensation in stops for 8-bit output"),
+        )
+        .arg(
+            Arg::with_name("white")
+                .long("white")
+                .value_name("WHITE")
+                .takes_value(true)
+                .help("Sets white point of the reinhard-extended and hable operators"),
+        )
         .arg(
             Arg::with_name("output")
                 .short("o")
@@ -202,7 +340,7 @@ fn match_args() -> ArgMatches<'static> {
                 .value_name("FILE")
                 .takes_value(true)
                 .default_value("render.ppm")
-                .help("File to save rendered image"),
+                .help("File to save rendered image (.exr and .hdr keep linear radiance)"),
         )
         .get_matches()
 }
@@ -1,9 +1,9 @@
 use raytracer::{
     bvh::BvhNode,
     camera::Camera,
-    hittable::{Hittable, HittableList, RotateY, Translate},
+    hittable::{FlipFace, HittableList, Transform},
     material::*,
-    math::{Color, Point3, Vec3},
+    math::{Color, Mat4, Point3, Vec3},
     primitives::*,
     scene::*,
 };
@@ -24,18 +24,32 @@ pub fn cornell_box(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         0.0,
         555.0,
         555.0,
-        mat_green.clone(),
+        Some(mat_green.clone()),
+    ));
+    scene_objects.add(YzPlane::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        0.0,
+        Some(mat_red.clone()),
     ));
-    scene_objects.add(YzPlane::new(0.0, 555.0, 0.0, 555.0, 0.0, mat_red.clone()));
 
-    scene_objects.add(XzPlane::new(0.0, 555.0, 0.0, 555.0, 0.0, mat_white.clone()));
+    scene_objects.add(XzPlane::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        0.0,
+        Some(mat_white.clone()),
+    ));
     scene_objects.add(XzPlane::new(
         0.0,
         555.0,
         0.0,
         555.0,
         555.0,
-        mat_white.clone(),
+        Some(mat_white.clone()),
     ));
 
     scene_objects.add(XyPlane::new(
@@ -44,36 +58,35 @@ pub fn cornell_box(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         0.0,
         555.0,
         555.0,
-        mat_white.clone(),
+        Some(mat_white.clone()),
     ));
 
     // top light
-    scene_objects.add(XzPlane::new(
+    // lights only emit from their front face, so flip it to face down into the scene
+    scene_objects.add(FlipFace::new(Arc::new(XzPlane::new(
         213.0,
         343.0,
         227.0,
         332.0,
         554.0,
-        mat_light.clone(),
-    ));
+        Some(mat_light.clone()),
+    ))));
 
     // boxes
     let box0 = Arc::new(AaBox::new(
         Point3::new(0.0, 0.0, 0.0),
         Point3::new(165.0, 330.0, 165.0),
-        mat_white.clone(),
+        Some(mat_white.clone()),
     ));
-    let box0: Arc<dyn Hittable> = Arc::new(RotateY::new(box0.clone(), 15.0));
-    let box0 = Translate::new(box0.clone(), Vec3::new(265.0, 0.0, 295.0));
+    let box0 = Transform::new(box0, Mat4::rotation_y(15.0)).translate(Vec3::new(265.0, 0.0, 295.0));
     scene_objects.add(box0);
 
     let box1 = Arc::new(AaBox::new(
         Point3::new(0.0, 0.0, 0.0),
         Point3::new(165.0, 165.0, 165.0),
-        mat_white.clone(),
+        Some(mat_white.clone()),
     ));
-    let box1: Arc<dyn Hittable> = Arc::new(RotateY::new(box1.clone(), -18.0));
-    let box1 = Translate::new(box1.clone(), Vec3::new(130.0, 0.0, 65.0));
+    let box1 = Transform::new(box1, Mat4::rotation_y(-18.0)).translate(Vec3::new(130.0, 0.0, 65.0));
     scene_objects.add(box1);
 
     if use_bvh {
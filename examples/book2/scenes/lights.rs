@@ -19,7 +19,7 @@ pub fn lights_rgb(camera: Camera, background: Color, use_bvh: bool) -> Scene {
     scene_objects.add(Sphere::new(
         Point3::new(0.0, 0.0, 0.0),
         2.0,
-        mat_perlin.clone(),
+        Some(mat_perlin.clone()),
     ));
 
     let mat_red_light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(1.0, 0.0, 0.0)));
@@ -29,7 +29,7 @@ pub fn lights_rgb(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         -1.5,
         1.5,
         -6.5,
-        mat_red_light.clone(),
+        Some(mat_red_light.clone()),
     ));
 
     let mat_blue_light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(0.0, 0.0, 1.0)));
@@ -39,7 +39,7 @@ pub fn lights_rgb(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         -1.5,
         1.5,
         6.5,
-        mat_blue_light.clone(),
+        Some(mat_blue_light.clone()),
     ));
 
     let mat_green_light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(0.0, 1.0, 0.0)));
@@ -49,7 +49,7 @@ pub fn lights_rgb(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         -1.5,
         1.5,
         -6.5,
-        mat_green_light.clone(),
+        Some(mat_green_light.clone()),
     ));
 
     let mat_white_light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
@@ -59,7 +59,7 @@ pub fn lights_rgb(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         -1.5,
         1.5,
         6.5,
-        mat_white_light.clone(),
+        Some(mat_white_light.clone()),
     ));
 
     let mat_bg: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
@@ -69,7 +69,7 @@ pub fn lights_rgb(camera: Camera, background: Color, use_bvh: bool) -> Scene {
         -100.0,
         100.0,
         -3.0,
-        mat_bg.clone(),
+        Some(mat_bg.clone()),
     ));
 
     if use_bvh {
@@ -13,3 +13,17 @@ pub use imagetexture::*;
 pub use lights::*;
 pub use perlin::*;
 pub use random::*;
+
+use raytracer::{
+    light::Sky,
+    math::{Color, Vec3},
+};
+
+/// Afternoon daylight for the outdoor scenes, with the sun off to the side of the camera.
+fn daylight() -> Sky {
+    Sky::new(
+        Vec3::new(0.5, 0.7, 1.0),
+        Sky::DEFAULT_TURBIDITY,
+        Color::new(0.5, 0.5, 0.5),
+    )
+}
@@ -20,21 +20,22 @@ pub fn two_perlin_spheres(camera: Camera, use_bvh: bool) -> Scene {
     scene_objects.add(Sphere::new(
         Point3::new(0.0, -1000.0, 0.0),
         1000.0,
-        mat_perlin.clone(),
+        Some(mat_perlin.clone()),
     ));
 
     scene_objects.add(Sphere::new(
         Point3::new(0.0, 2.0, 0.0),
         2.0,
-        mat_perlin.clone(),
+        Some(mat_perlin.clone()),
     ));
 
-    let background = Color::new(0.7, 0.8, 1.0);
-    if use_bvh {
+    let mut scene = if use_bvh {
         let bvh = BvhNode::from_list(&scene_objects, 0.0, 1.0);
         println!("Created root BvhNode: {}", bvh);
-        Scene::new(bvh.into(), background, camera)
+        Scene::new(bvh.into(), Color::default(), camera)
     } else {
-        Scene::new(scene_objects, background, camera)
-    }
+        Scene::new(scene_objects, Color::default(), camera)
+    };
+    scene.set_environment(Box::new(super::daylight()));
+    scene
 }
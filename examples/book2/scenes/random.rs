@@ -1,4 +1,4 @@
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use raytracer::{
     bvh::BvhNode,
     camera::Camera,
@@ -22,10 +22,11 @@ pub fn random_scene(camera: Camera, use_bvh: bool) -> Scene {
     scene_objects.add(Sphere::new(
         Point3::new(0.0, -1000.0, 0.0),
         1000.0,
-        mat_ground.clone(),
+        Some(mat_ground.clone()),
     ));
 
-    let mut rng = thread_rng();
+    // a fixed seed keeps the generated scene the same between runs
+    let mut rng = StdRng::seed_from_u64(0);
     for a in -11..11 {
         for b in -11..11 {
             let choose_mat: f64 = rng.gen();
@@ -39,25 +40,32 @@ pub fn random_scene(camera: Camera, use_bvh: bool) -> Scene {
                 let material: Arc<dyn Material>;
                 if choose_mat < 0.6 {
                     // diffuse
-                    let albedo = Vec3::random() * Vec3::random();
+                    let albedo = Vec3::random_with(&mut rng) * Vec3::random_with(&mut rng);
                     material = Arc::new(Lambertian::new(albedo));
-                    scene_objects.add(Sphere::new(center, 0.2, material));
+                    scene_objects.add(Sphere::new(center, 0.2, Some(material)));
                 } else if choose_mat < 0.8 {
                     // moving diffuse sphere
-                    let albedo = Vec3::random() * Vec3::random();
+                    let albedo = Vec3::random_with(&mut rng) * Vec3::random_with(&mut rng);
                     material = Arc::new(Lambertian::new(albedo));
                     let center2 = center + Vec3::new(0.0, rng.gen_range(0.0, 0.5), 0.0);
-                    scene_objects.add(MovingSphere::new(center, center2, 0.2, 0.0, 1.0, material));
+                    scene_objects.add(MovingSphere::new(
+                        center,
+                        center2,
+                        0.2,
+                        0.0,
+                        1.0,
+                        Some(material),
+                    ));
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = Vec3::random_in_range(0.5, 1.0);
-                    let fuzz: f64 = rng.gen_range(0.0, 0.5);
-                    material = Arc::new(Metal::new(albedo, fuzz));
-                    scene_objects.add(Sphere::new(center, 0.2, material));
+                    let albedo = Vec3::random_in_range_with(&mut rng, 0.5, 1.0);
+                    let roughness: f64 = rng.gen_range(0.0, 0.5);
+                    material = Arc::new(Metal::new(albedo, roughness));
+                    scene_objects.add(Sphere::new(center, 0.2, Some(material)));
                 } else {
                     // glass
                     material = Arc::new(Dielectric::new(1.5));
-                    scene_objects.add(Sphere::new(center, 0.2, material));
+                    scene_objects.add(Sphere::new(center, 0.2, Some(material)));
                 }
             }
         }
@@ -67,25 +75,34 @@ pub fn random_scene(camera: Camera, use_bvh: bool) -> Scene {
     let lambert: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)));
     let metal: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0));
 
-    scene_objects.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.25, glass.clone()));
+    scene_objects.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.25,
+        Some(glass.clone()),
+    ));
     scene_objects.add(Sphere::new(
         Point3::new(-4.0, 1.0, 3.0),
         1.0,
-        lambert.clone(),
+        Some(lambert.clone()),
     ));
     scene_objects.add(Sphere::new(
         Point3::new(-4.0, 1.0, -3.0),
         1.0,
-        lambert.clone(),
+        Some(lambert.clone()),
+    ));
+    scene_objects.add(Sphere::new(
+        Point3::new(4.0, 1.0, -2.0),
+        1.0,
+        Some(metal.clone()),
     ));
-    scene_objects.add(Sphere::new(Point3::new(4.0, 1.0, -2.0), 1.0, metal.clone()));
 
-    let background = Color::new(0.7, 0.8, 1.0);
-    if use_bvh {
+    let mut scene = if use_bvh {
         let bvh = BvhNode::from_list(&scene_objects, 0.0, 1.0);
         println!("Created root BvhNode: {}", bvh);
-        Scene::new(bvh.into(), background, camera)
+        Scene::new(bvh.into(), Color::default(), camera)
     } else {
-        Scene::new(scene_objects, background, camera)
-    }
+        Scene::new(scene_objects, Color::default(), camera)
+    };
+    scene.set_environment(Box::new(super::daylight()));
+    scene
 }
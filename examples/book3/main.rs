@@ -1,8 +1,11 @@
 use clap::{App, Arg, ArgMatches};
-use image;
 use raytracer::{
     camera::Camera,
+    film::{filter_by_name, tone_map_by_name, FILTER_NAMES, TONE_MAP_NAMES},
+    integrator::{integrator_by_name, INTEGRATOR_NAMES},
     math::{Color, Point3, Vec3},
+    sampler::{sampler_by_name, SAMPLER_NAMES},
+    scene::AdaptiveOptions,
 };
 
 mod scenes;
@@ -28,6 +31,17 @@ fn main() {
     // Parse filename
     let filename = matches.value_of("output").unwrap();
 
+    // Parse output args
+    let white = matches.value_of("white").map(|w| w.parse().unwrap());
+    let tone_map = tone_map_by_name(matches.value_of("tonemap").unwrap(), white).unwrap();
+    let integrator = integrator_by_name(matches.value_of("integrator").unwrap()).unwrap();
+    let seed: u64 = matches.value_of("seed").unwrap().parse().unwrap();
+    let sampler = sampler_by_name(matches.value_of("sampler").unwrap(), seed).unwrap();
+    let filter_radius = matches.value_of("filterradius").map(|r| r.parse().unwrap());
+    let filter = filter_by_name(matches.value_of("filter").unwrap(), filter_radius)
+        .expect("filter radius must be positive");
+    let exposure: f32 = matches.value_of("exposure").unwrap().parse().unwrap();
+
     // Parse scene
     let scene_num: i32 = matches.value_of("scene").unwrap().parse().unwrap();
 
@@ -68,16 +82,27 @@ fn main() {
     );
 
     // Render
-    let scene = scene_fn(camera);
-    let image = scene.render(image_width, image_height, samples_per_pixel, max_depth);
-    image::save_buffer(
-        filename,
-        &image,
-        image_width as u32,
-        image_height as u32,
-        image::ColorType::Rgb8,
-    )
-    .unwrap();
+    let mut scene = scene_fn(camera);
+    scene.set_integrator(integrator);
+    scene.set_sampler(sampler);
+    scene.set_filter(filter);
+    let film = if matches.is_present("adaptive") {
+        let options = AdaptiveOptions {
+            min_samples: matches.value_of("minsamples").unwrap().parse().unwrap(),
+            max_samples: samples_per_pixel,
+            threshold: matches.value_of("threshold").unwrap().parse().unwrap(),
+            ..AdaptiveOptions::default()
+        };
+        scene.render_adaptive(image_width, image_height, options, max_depth)
+    } else {
+        scene.render(image_width, image_height, samples_per_pixel, max_depth)
+    };
+    film.save_with(filename, tone_map.as_ref(), exposure)
+        .unwrap();
+
+    if let Some(heatmap) = matches.value_of("heatmap") {
+        film.sample_heatmap().save(heatmap).unwrap();
+    }
 }
 
 fn match_args() -> ArgMatches<'static> {
@@ -136,7 +161,35 @@ fn match_args() -> ArgMatches<'static> {
                 .value_name("SAMPLES")
                 .takes_value(true)
                 .default_value("500")
-                .help("Sets number of samples per pixel"),
+                .help("Sets number of samples per pixel (the most per pixel with --adaptive)"),
+        )
+        .arg(
+            Arg::with_name("adaptive")
+                .long("adaptive")
+                .help("Stops sampling each pixel once its estimated error is below --threshold"),
+        )
+        .arg(
+            Arg::with_name("minsamples")
+                .long("min-samples")
+                .value_name("SAMPLES")
+                .takes_value(true)
+                .default_value("16")
+                .help("Sets number of samples per pixel before adaptive sampling may stop"),
+        )
+        .arg(
+            Arg::with_name("threshold")
+                .long("threshold")
+                .value_name("ERROR")
+                .takes_value(true)
+                .default_value("0.05")
+                .help("Sets relative error at which adaptive sampling stops"),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .long("heatmap")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Saves an image of the number of samples taken in each pixel"),
         )
         .arg(
             Arg::with_name("bounces")
@@ -147,6 +200,75 @@ fn match_args() -> ArgMatches<'static> {
                 .default_value("50")
                 .help("Sets max bounces (depth) for each raycast"),
         )
+        .arg(
+            Arg::with_name("integrator")
+                .long("integrator")
+                .value_name("INTEGRATOR")
+                .takes_value(true)
+                .possible_values(INTEGRATOR_NAMES)
+                .default_value("path")
+                .help("Sets light transport algorithm (ao and normals are for previews and debugging)"),
+        )
+        .arg(
+            Arg::with_name("sampler")
+                .long("sampler")
+                .value_name("SAMPLER")
+                .takes_value(true)
+                .possible_values(SAMPLER_NAMES)
+                .default_value("independent")
+                .help("Sets sample generator for pixel, lens, time, light and BSDF samples"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .default_value("0")
+                .help("Sets random seed; renders with the same seed and options are identical"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .value_name("FILTER")
+                .takes_value(true)
+                .possible_values(FILTER_NAMES)
+                .default_value("box")
+                .help("Sets reconstruction filter samples are splatted onto pixels with"),
+        )
+        .arg(
+            Arg::with_name("filterradius")
+                .long("filter-radius")
+                .value_name("PIXELS")
+                .takes_value(true)
+                .help("Overrides the reconstruction filter's radius"),
+        )
+        .arg(
+            Arg::with_name("tonemap")
+                .short("t")
+                .long("tonemap")
+                .value_name("OPERATOR")
+                .takes_value(true)
+                .possible_values(TONE_MAP_NAMES)
+                .default_value("clamp")
+                .help("Sets tone mapping operator for 8-bit output"),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .short("e")
+                .long("exposure")
+                .value_name("EV")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .default_value("0")
+                .help("Sets exposure compensation in stops for 8-bit output"),
+        )
+        .arg(
+            Arg::with_name("white")
+                .long("white")
+                .value_name("WHITE")
+                .takes_value(true)
+                .help("Sets white point of the reinhard-extended and hable operators"),
+        )
         .arg(
             Arg::with_name("output")
                 .short("o")
@@ -154,7 +276,7 @@ fn match_args() -> ArgMatches<'static> {
                 .value_name("FILE")
                 .takes_value(true)
                 .default_value("render.ppm")
-                .help("File to save rendered image"),
+                .help("File to save rendered image (.exr and .hdr keep linear radiance)"),
         )
         .arg(
             Arg::with_name("scene")
@@ -3,7 +3,7 @@ use raytracer::{
     camera::Camera,
     hittable::*,
     material::*,
-    math::{Color, Point3, Vec3},
+    math::{Color, Mat4, Point3, Vec3},
     primitives::*,
     scene::*,
 };
@@ -75,13 +75,12 @@ pub fn cornell_box(camera: Camera, background: Color) -> Scene {
 
     // boxes
     //let mat_metal: Arc<dyn Material> = Arc::new(Metal::new(Color::new(0.8, 0.85, 0.88), 0.0));
-    let box0 = AaBox::new(
+    let box0 = Arc::new(AaBox::new(
         Point3::new(0.0, 0.0, 0.0),
         Point3::new(165.0, 330.0, 165.0),
         Some(mat_white.clone()),
-    );
-    let box0 = RotateY::new(box0, 15.0);
-    let box0 = Translate::new(box0, Vec3::new(265.0, 0.0, 295.0));
+    ));
+    let box0 = Transform::new(box0, Mat4::rotation_y(15.0)).translate(Vec3::new(265.0, 0.0, 295.0));
     scene_objects.add(box0);
 
     // glass sphere
@@ -95,9 +94,5 @@ pub fn cornell_box(camera: Camera, background: Color) -> Scene {
     let bvh = BvhNode::from_list(&scene_objects, 0.0, 1.0);
     println!("Created root BvhNode: {}", bvh);
 
-    let mut lights = HittableList::new();
-
-    lights.add(XzPlane::new(213.0, 343.0, 227.0, 332.0, 554.0, None));
-    lights.add(Sphere::new(Point3::new(190.0, 90.0, 190.0), 90.0, None));
-    Scene::new(bvh.into(), Arc::new(lights), background, camera)
+    Scene::new(bvh.into(), background, camera)
 }
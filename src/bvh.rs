@@ -1,15 +1,109 @@
-use crate::hittable::{HitRecord, Hittable, HittableList};
-use crate::math::{Aabb, Ray};
-use rand::{thread_rng, Rng};
+use crate::hittable::{HitRecord, Hittable, HittableList, Instance};
+use crate::light::LightList;
+use crate::material::Material;
+use crate::math::{Aabb, Mat4, Point3, Ray};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
 use std::cmp::Ordering;
 use std::sync::Arc;
+
+/// Size of the fixed traversal stack, which bounds the depth of the tree.
+const STACK_SIZE: usize = 64;
+
+/// Depth after which the SAH builder stops following the heuristic and splits by count.
+const SAH_MAX_DEPTH: usize = 32;
+
+/// Settings for building a [`BvhNode`] with the surface area heuristic (SAH).
+///
+/// Costs are relative to each other: a split is only made when the estimated cost of traversing
+/// the two children is lower than intersecting every object in a single leaf.
+#[derive(Debug, Clone, Copy)]
+pub struct SahOptions {
+    /// Largest number of objects a leaf may hold. Nodes with more objects are always split.
+    pub max_leaf_size: usize,
+    /// Cost of testing a ray against an interior node's bounding box.
+    pub traversal_cost: f64,
+    /// Cost of testing a ray against a single object.
+    pub intersection_cost: f64,
+    /// Number of buckets object centroids are binned into along each axis.
+    pub bins: usize,
+}
+
+impl Default for SahOptions {
+    fn default() -> Self {
+        Self {
+            max_leaf_size: 4,
+            traversal_cost: 0.125,
+            intersection_cost: 1.0,
+            bins: 12,
+        }
+    }
+}
+
+/// A bounding volume hierarchy over a set of [`Hittable`] objects.
+///
+/// The tree is stored as a flat array of 32-byte nodes in depth-first order: an interior node's
+/// first child immediately follows it and only the second child's index is stored. Traversal
+/// visits the child nearest the ray origin first and narrows `t_max` to the closest hit found so
+/// far, so far children behind an earlier hit are skipped.
 pub struct BvhNode {
-    left: Option<Box<BvhNode>>,
-    right: Option<Box<BvhNode>>,
-    hittable: Option<Arc<dyn Hittable>>,
+    nodes: Vec<LinearNode>,
+    objects: Vec<Arc<dyn Hittable>>,
     aabb: Aabb,
 }
 
+/// A flattened BVH node.
+///
+/// Bounds are stored as `f32`, rounded outwards so that they always contain the exact bounds.
+#[repr(C)]
+struct LinearNode {
+    min: [f32; 3],
+    max: [f32; 3],
+    /// Index of the first object for a leaf, or of the second child for an interior node.
+    offset: u32,
+    /// Number of objects in a leaf, zero for interior nodes.
+    count: u16,
+    /// Axis the node's children were split along.
+    axis: u8,
+    _pad: u8,
+}
+
+/// Temporary pointer-based tree produced by the builders before it is flattened.
+enum BuildNode {
+    Leaf {
+        objects: Vec<Arc<dyn Hittable>>,
+        aabb: Aabb,
+    },
+    Interior {
+        left: Box<BuildNode>,
+        right: Box<BuildNode>,
+        axis: usize,
+        aabb: Aabb,
+    },
+}
+
+impl BuildNode {
+    fn leaf(objects: Vec<Arc<dyn Hittable>>, aabb: Aabb) -> Self {
+        BuildNode::Leaf { objects, aabb }
+    }
+
+    fn interior(left: BuildNode, right: BuildNode, axis: usize) -> Self {
+        let aabb = Aabb::surrounding_box(&left.aabb(), &right.aabb());
+        BuildNode::Interior {
+            left: Box::new(left),
+            right: Box::new(right),
+            axis,
+            aabb,
+        }
+    }
+
+    fn aabb(&self) -> Aabb {
+        match self {
+            BuildNode::Leaf { aabb, .. } | BuildNode::Interior { aabb, .. } => *aabb,
+        }
+    }
+}
+
 impl BvhNode {
     pub fn from_list(list: &HittableList, t0: f64, t1: f64) -> Self {
         Self::new(list.objects(), 0, list.objects().len(), t0, t1)
@@ -22,7 +116,11 @@ impl BvhNode {
         t0: f64,
         t1: f64,
     ) -> Self {
-        Self::new_impl(&mut src_objects.clone(), start, end, t0, t1)
+        assert!(end > start, "cannot build a BvhNode without objects");
+        // split axes are random, but seeded so the same list always produces the same tree
+        let mut rng = Pcg32::seed_from_u64(0);
+        let root = Self::new_impl(&mut src_objects.clone(), start, end, t0, t1, &mut rng);
+        Self::flatten(root)
     }
 
     fn new_impl(
@@ -31,85 +129,306 @@ impl BvhNode {
         end: usize,
         t0: f64,
         t1: f64,
-    ) -> Self {
+        rng: &mut Pcg32,
+    ) -> BuildNode {
         let object_span = end - start;
 
-        let left: Box<BvhNode>;
-        let right: Box<BvhNode>;
         if object_span == 1 {
             let hittable = objects[start].clone();
-            return Self {
-                left: None,
-                right: None,
-                aabb: hittable.bounding_box(t0, t1).unwrap(),
-                hittable: Some(hittable),
-            };
+            let aabb = hittable.bounding_box(t0, t1).unwrap();
+            return BuildNode::leaf(vec![hittable], aabb);
+        }
+
+        let axis = sort_span(&mut objects[start..end], rng);
+
+        let (left, right) = if object_span == 2 {
+            (
+                BuildNode::leaf(
+                    vec![objects[start].clone()],
+                    objects[start].bounding_box(t0, t1).unwrap(),
+                ),
+                BuildNode::leaf(
+                    vec![objects[start + 1].clone()],
+                    objects[start + 1].bounding_box(t0, t1).unwrap(),
+                ),
+            )
         } else {
-            sort_span(&mut objects[start..end]);
-
-            if object_span == 2 {
-                left = Box::new(BvhNode {
-                    left: None,
-                    right: None,
-                    hittable: Some(objects[start].clone()),
-                    aabb: objects[start].bounding_box(t0, t1).unwrap(),
-                });
-                right = Box::new(BvhNode {
-                    left: None,
-                    right: None,
-                    hittable: Some(objects[start + 1].clone()),
-                    aabb: objects[start + 1].bounding_box(t0, t1).unwrap(),
-                });
-            } else {
-                let mid = start + object_span / 2;
-
-                left = Box::new(Self::new_impl(objects, start, mid, t0, t1));
-                right = Box::new(Self::new_impl(objects, mid, end, t0, t1));
-            }
+            let mid = start + object_span / 2;
+
+            (
+                Self::new_impl(objects, start, mid, t0, t1, rng),
+                Self::new_impl(objects, mid, end, t0, t1, rng),
+            )
+        };
+
+        BuildNode::interior(left, right, axis)
+    }
+
+    /// Builds a BVH with a binned surface area heuristic.
+    ///
+    /// Unlike [`BvhNode::from_list`], which splits each node at the median along a random axis,
+    /// every split is chosen by evaluating `options.bins` candidate planes on all three axes.
+    pub fn from_list_sah(list: &HittableList, t0: f64, t1: f64, options: SahOptions) -> Self {
+        Self::new_sah(list.objects(), t0, t1, options)
+    }
+
+    /// Builds a BVH over `objects` with a binned surface area heuristic.
+    ///
+    /// See [`BvhNode::from_list_sah`].
+    pub fn new_sah(objects: &[Arc<dyn Hittable>], t0: f64, t1: f64, options: SahOptions) -> Self {
+        assert!(
+            !objects.is_empty(),
+            "cannot build a BvhNode without objects"
+        );
+
+        let mut build_objects: Vec<BuildObject> = objects
+            .iter()
+            .map(|hittable| {
+                let aabb = hittable.bounding_box(t0, t1).unwrap();
+                BuildObject {
+                    hittable: hittable.clone(),
+                    aabb,
+                    centroid: aabb.centroid(),
+                }
+            })
+            .collect();
+
+        let options = SahOptions {
+            max_leaf_size: options.max_leaf_size.max(1),
+            bins: options.bins.max(2),
+            ..options
+        };
+
+        Self::flatten(Self::build_sah(&mut build_objects, &options, 0))
+    }
+
+    fn build_sah(objects: &mut [BuildObject], options: &SahOptions, depth: usize) -> BuildNode {
+        let aabb = objects.iter().skip(1).fold(objects[0].aabb, |acc, o| {
+            Aabb::surrounding_box(&acc, &o.aabb)
+        });
+
+        if objects.len() == 1 {
+            return BuildNode::leaf(vec![objects[0].hittable.clone()], aabb);
         }
 
-        let box_left = left.aabb;
-        let box_right = right.aabb;
+        let (centroid_min, centroid_max) = objects.iter().fold(
+            (objects[0].centroid, objects[0].centroid),
+            |(mut min, mut max), o| {
+                for a in 0..3 {
+                    min[a] = min[a].min(o.centroid[a]);
+                    max[a] = max[a].max(o.centroid[a]);
+                }
+                (min, max)
+            },
+        );
 
-        Self {
-            left: Some(left),
-            right: Some(right),
-            hittable: None,
-            aabb: Aabb::surrounding_box(&box_left, &box_right),
+        let bin_of = |centroid: &Point3, axis: usize| -> usize {
+            let extent = centroid_max[axis] - centroid_min[axis];
+            let bin =
+                ((centroid[axis] - centroid_min[axis]) / extent * options.bins as f64) as usize;
+            bin.min(options.bins - 1)
+        };
+
+        // find the cheapest split plane between bins on any axis
+        let mut best: Option<(usize, usize, f64)> = None;
+        for axis in 0..3 {
+            if centroid_max[axis] - centroid_min[axis] <= 0.0 {
+                continue;
+            }
+
+            let mut bin_boxes: Vec<Option<Aabb>> = vec![None; options.bins];
+            let mut bin_counts = vec![0usize; options.bins];
+            for object in objects.iter() {
+                let bin = bin_of(&object.centroid, axis);
+                bin_counts[bin] += 1;
+                bin_boxes[bin] = Some(surround(bin_boxes[bin], &object.aabb));
+            }
+
+            // sweep from the right so each split can read the area of everything above it
+            let mut right_areas = vec![0.0; options.bins];
+            let mut right_counts = vec![0usize; options.bins];
+            let mut right_box: Option<Aabb> = None;
+            let mut right_count = 0;
+            for bin in (1..options.bins).rev() {
+                if let Some(ref bin_box) = bin_boxes[bin] {
+                    right_box = Some(surround(right_box, bin_box));
+                }
+                right_count += bin_counts[bin];
+                right_areas[bin] = right_box.map_or(0.0, |b| b.surface_area());
+                right_counts[bin] = right_count;
+            }
+
+            let mut left_box: Option<Aabb> = None;
+            let mut left_count = 0;
+            for split in 1..options.bins {
+                if let Some(ref bin_box) = bin_boxes[split - 1] {
+                    left_box = Some(surround(left_box, bin_box));
+                }
+                left_count += bin_counts[split - 1];
+
+                if left_count == 0 || right_counts[split] == 0 {
+                    continue;
+                }
+
+                let left_area = left_box.map_or(0.0, |b| b.surface_area());
+                let cost = options.traversal_cost
+                    + options.intersection_cost
+                        * (left_area * left_count as f64
+                            + right_areas[split] * right_counts[split] as f64)
+                        / aabb.surface_area();
+
+                match best {
+                    Some((_, _, best_cost)) if best_cost <= cost => {}
+                    _ => best = Some((axis, split, cost)),
+                }
+            }
         }
+
+        let leaf_cost = options.intersection_cost * objects.len() as f64;
+        let must_split = objects.len() > options.max_leaf_size;
+
+        let (axis, mid) = match best {
+            // past this depth, halve the object count at every level so that the tree can't
+            // outgrow the traversal stack, however unevenly the SAH would split it
+            _ if must_split && depth >= SAH_MAX_DEPTH => (0, objects.len() / 2),
+            Some((axis, split, cost)) if cost < leaf_cost || must_split => (
+                axis,
+                partition(objects, |o| bin_of(&o.centroid, axis) < split),
+            ),
+            // every centroid is identical, so no plane separates them; split by count instead
+            None if must_split => (0, objects.len() / 2),
+            _ => {
+                let leaf_objects = objects.iter().map(|o| o.hittable.clone()).collect();
+                return BuildNode::leaf(leaf_objects, aabb);
+            }
+        };
+
+        let (left, right) = objects.split_at_mut(mid);
+        BuildNode::interior(
+            Self::build_sah(left, options, depth + 1),
+            Self::build_sah(right, options, depth + 1),
+            axis,
+        )
     }
 
-    pub fn left(&self) -> &Option<Box<BvhNode>> {
-        &self.left
+    /// Lays out a build tree as a depth-first array of [`LinearNode`]s.
+    fn flatten(root: BuildNode) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            objects: Vec::new(),
+            aabb: root.aabb(),
+        };
+        bvh.flatten_node(root);
+        bvh
     }
 
-    pub fn right(&self) -> &Option<Box<BvhNode>> {
-        &self.right
+    fn flatten_node(&mut self, node: BuildNode) -> usize {
+        let index = self.nodes.len();
+        let aabb = node.aabb();
+        let (min, max) = (aabb.min(), aabb.max());
+        self.nodes.push(LinearNode {
+            min: [round_down(min[0]), round_down(min[1]), round_down(min[2])],
+            max: [round_up(max[0]), round_up(max[1]), round_up(max[2])],
+            offset: 0,
+            count: 0,
+            axis: 0,
+            _pad: 0,
+        });
+
+        match node {
+            BuildNode::Leaf { objects, .. } => {
+                let count = objects.len();
+                assert!(count <= u16::MAX as usize, "too many objects in BVH leaf");
+                self.nodes[index].offset = self.objects.len() as u32;
+                self.nodes[index].count = count as u16;
+                self.objects.extend(objects);
+            }
+            BuildNode::Interior {
+                left, right, axis, ..
+            } => {
+                self.flatten_node(*left);
+                let second = self.flatten_node(*right);
+                self.nodes[index].offset = second as u32;
+                self.nodes[index].axis = axis as u8;
+            }
+        }
+
+        index
     }
 
+    /// Number of objects stored in the tree's leaves.
     pub fn len(&self) -> usize {
-        let left = if let Some(ref node) = self.left {
-            node.len()
-        } else {
-            0
-        };
-        let right = if let Some(ref node) = self.right {
-            node.len()
-        } else {
-            0
-        };
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Number of nodes in the tree, including leaves.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// An object being sorted into a SAH-built [`BvhNode`], with its bounds cached.
+struct BuildObject {
+    hittable: Arc<dyn Hittable>,
+    aabb: Aabb,
+    centroid: Point3,
+}
 
-        left + right + if self.hittable.is_some() { 1 } else { 0 }
+fn surround(aabb: Option<Aabb>, other: &Aabb) -> Aabb {
+    match aabb {
+        Some(ref aabb) => Aabb::surrounding_box(aabb, other),
+        None => *other,
+    }
+}
+
+/// Moves every object matching `pred` to the front, returning the number of matches.
+fn partition<F>(objects: &mut [BuildObject], pred: F) -> usize
+where
+    F: Fn(&BuildObject) -> bool,
+{
+    let mut mid = 0;
+    for i in 0..objects.len() {
+        if pred(&objects[i]) {
+            objects.swap(i, mid);
+            mid += 1;
+        }
     }
+    mid
 }
 
-fn sort_span(span: &mut [Arc<dyn Hittable>]) {
-    match thread_rng().gen_range(0, 3) {
+/// Sorts `span` along a random axis, returning the axis.
+fn sort_span(span: &mut [Arc<dyn Hittable>], rng: &mut Pcg32) -> usize {
+    let axis = rng.gen_range(0, 3);
+    match axis {
         0 => span.sort_by(box_compare_x),
         1 => span.sort_by(box_compare_y),
         2 => span.sort_by(box_compare_z),
         _ => panic!(),
     };
+    axis
+}
+
+/// Converts to `f32`, rounding towards negative infinity.
+fn round_down(value: f64) -> f32 {
+    let rounded = value as f32;
+    if rounded as f64 <= value {
+        rounded
+    } else if rounded > 0.0 {
+        f32::from_bits(rounded.to_bits() - 1)
+    } else if rounded < 0.0 {
+        f32::from_bits(rounded.to_bits() + 1)
+    } else {
+        -f32::from_bits(1)
+    }
+}
+
+/// Converts to `f32`, rounding towards positive infinity.
+fn round_up(value: f64) -> f32 {
+    -round_down(-value)
 }
 
 fn box_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>, axis: usize) -> Ordering {
@@ -130,56 +449,222 @@ fn box_compare_z(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>) -> Ordering {
 
 impl Hittable for BvhNode {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        if !self.aabb.hit(ray, t_min, t_max) {
-            return None;
-        }
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let inv_dir = [
+            1.0 / direction.x(),
+            1.0 / direction.y(),
+            1.0 / direction.z(),
+        ];
+        let dir_is_neg = [inv_dir[0] < 0.0, inv_dir[1] < 0.0, inv_dir[2] < 0.0];
 
-        if self.hittable.is_some() {
-            return self.hittable.as_ref().unwrap().hit(ray, t_min, t_max);
-        }
+        let mut closest_so_far = t_max;
+        let mut hit: Option<HitRecord> = None;
 
-        let hit_left = if let Some(ref left) = self.left {
-            left.hit(ray, t_min, t_max)
-        } else {
-            None
-        };
-        let hit_right = if let Some(ref right) = self.right {
-            right.hit(ray, t_min, t_max)
-        } else {
-            None
-        };
+        let mut stack = [0usize; STACK_SIZE];
+        let mut stack_len = 0;
+        let mut current = 0;
 
-        if hit_left.is_some() && hit_right.is_some() {
-            return if hit_left.as_ref().unwrap().t < hit_right.as_ref().unwrap().t {
-                hit_left
-            } else {
-                hit_right
-            };
-        } else {
-            hit_left.or(hit_right)
+        loop {
+            let node = &self.nodes[current];
+
+            if node.hit(&origin, &inv_dir, t_min, closest_so_far) {
+                if node.count > 0 {
+                    let first = node.offset as usize;
+                    for object in self.objects[first..first + node.count as usize].iter() {
+                        if let Some(temp_rec) = object.hit(ray, t_min, closest_so_far) {
+                            closest_so_far = temp_rec.t;
+                            hit = Some(temp_rec);
+                        }
+                    }
+                } else {
+                    // visit the near child first and come back for the far one
+                    let (near, far) = if dir_is_neg[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
         }
+
+        hit
     }
 
     #[allow(unused_variables)]
     fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
         Some(self.aabb)
     }
+
+    fn add_lights(
+        &self,
+        _this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        for object in self.objects.iter() {
+            object.add_lights(object, material, lights);
+        }
+    }
+}
+
+impl LinearNode {
+    /// Slab test against the node's bounds, using the ray's precomputed inverse direction.
+    fn hit(&self, origin: &Point3, inv_dir: &[f64; 3], mut t_min: f64, mut t_max: f64) -> bool {
+        for a in 0..3 {
+            let t0 = (self.min[a] as f64 - origin[a]) * inv_dir[a];
+            let t1 = (self.max[a] as f64 - origin[a]) * inv_dir[a];
+            t_min = t0.min(t1).max(t_min);
+            t_max = t0.max(t1).min(t_max);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl std::fmt::Display for BvhNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "BvhNode {{ left: {}, right: {}, hittable:{}, aabb: {:?}, len: {} }} ",
-            if self.left.is_some() { "Some" } else { "None" },
-            if self.right.is_some() { "Some" } else { "None" },
-            if self.hittable.is_some() {
-                "Some"
-            } else {
-                "None"
-            },
+            "BvhNode {{ nodes: {}, aabb: {:?}, len: {} }} ",
+            self.node_count(),
             self.aabb,
             self.len()
         )
     }
 }
+
+/// A two-level acceleration structure: a top-level BVH (TLAS) over [`Instance`]s.
+///
+/// Each instance refers to a shared bottom-level object, typically a [`BvhNode`] built once per
+/// model, and rays are moved into the object's space when they reach an instance. Only the
+/// small top-level tree over instance bounding boxes is rebuilt when an instance moves, the
+/// bottom-level trees are left untouched.
+///
+/// A `TopLevelBvh` may be empty, in which case every ray misses it.
+pub struct TopLevelBvh {
+    instances: Vec<Arc<Instance>>,
+    /// `None` while there are no instances to build a tree over.
+    bvh: Option<BvhNode>,
+    t0: f64,
+    t1: f64,
+}
+
+impl TopLevelBvh {
+    pub fn new(instances: Vec<Instance>, t0: f64, t1: f64) -> Self {
+        let instances: Vec<Arc<Instance>> = instances.into_iter().map(Arc::new).collect();
+        let bvh = Self::build(&instances, t0, t1);
+
+        Self {
+            instances,
+            bvh,
+            t0,
+            t1,
+        }
+    }
+
+    fn build(instances: &[Arc<Instance>], t0: f64, t1: f64) -> Option<BvhNode> {
+        if instances.is_empty() {
+            return None;
+        }
+
+        let objects: Vec<Arc<dyn Hittable>> = instances
+            .iter()
+            .map(|instance| instance.clone() as Arc<dyn Hittable>)
+            .collect();
+
+        // instances are expensive to test, so give each its own leaf
+        let options = SahOptions {
+            max_leaf_size: 1,
+            ..SahOptions::default()
+        };
+        Some(BvhNode::new_sah(&objects, t0, t1, options))
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn instance(&self, index: usize) -> &Instance {
+        &self.instances[index]
+    }
+
+    pub fn instances(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter().map(|instance| instance.as_ref())
+    }
+
+    /// Moves the instance at `index` and rebuilds the top-level tree.
+    pub fn set_transform(&mut self, index: usize, matrix: Mat4) {
+        self.set_transforms(std::iter::once((index, matrix)));
+    }
+
+    /// Moves several instances at once, rebuilding the top-level tree a single time.
+    pub fn set_transforms(&mut self, transforms: impl IntoIterator<Item = (usize, Mat4)>) {
+        for (index, matrix) in transforms {
+            self.instances[index] = Arc::new(self.instances[index].with_matrix(matrix));
+        }
+        self.bvh = Self::build(&self.instances, self.t0, self.t1);
+    }
+
+    /// Adds an instance and rebuilds the top-level tree.
+    pub fn push(&mut self, instance: Instance) {
+        self.instances.push(Arc::new(instance));
+        self.bvh = Self::build(&self.instances, self.t0, self.t1);
+    }
+
+    /// The top-level tree, whose objects are the instances, or `None` if there are none.
+    pub fn top_level(&self) -> Option<&BvhNode> {
+        self.bvh.as_ref()
+    }
+}
+
+impl Hittable for TopLevelBvh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.as_ref()?.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.bvh.as_ref()?.bounding_box(t0, t1)
+    }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(bvh) = &self.bvh {
+            bvh.add_lights(this, material, lights);
+        }
+    }
+}
+
+impl std::fmt::Display for TopLevelBvh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.bvh {
+            Some(bvh) => write!(
+                f,
+                "TopLevelBvh {{ instances: {}, top_level: {}}}",
+                self.len(),
+                bvh
+            ),
+            None => write!(f, "TopLevelBvh {{ instances: 0 }}"),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 #![allow(dead_code)]
 use crate::math::{Point3, Ray, Vec3};
-use rand::{thread_rng, Rng};
+use crate::sampler::Sampler;
 
 pub struct Camera {
     origin: Point3,
@@ -59,15 +59,17 @@ impl Camera {
         }
     }
 
-    /// Randomly samples a [`Ray`](crate::math::Ray) starting from a point on the camera lens given its position in screen space.
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disk();
+    /// Samples a [`Ray`](crate::math::Ray) starting from a point on the camera lens given its position in screen space.
+    ///
+    /// The lens position and shutter time are drawn from `sampler`.
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let rd = self.lens_radius * Vec3::in_unit_disk(sampler.get_2d());
         let offset = self.u * rd.x() + self.v * rd.y();
-        let mut rng = thread_rng();
+        let time = self.time_0 + sampler.get_1d() * (self.time_1 - self.time_0);
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-            rng.gen_range(self.time_0, self.time_1),
+            time,
         )
     }
 }
@@ -0,0 +1,276 @@
+//! Reconstruction filters for splatting samples onto the film
+
+use std::f64::consts::PI;
+
+/// Weights a sample's contribution to the pixels around it.
+///
+/// Offsets are measured in pixels from the pixel's center to the sample. Filters are zero
+/// beyond [`Filter::radius`] on either axis, and may be negative inside it.
+pub trait Filter: Send + Sync {
+    /// Half-width of the filter's support, in pixels.
+    fn radius(&self) -> f64;
+
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+}
+
+/// Names accepted by [`filter_by_name`].
+pub const FILTER_NAMES: &[&str] = &["box", "tent", "gaussian", "mitchell", "lanczos"];
+
+/// Looks up a filter by name. `radius` overrides the filter's default radius, and gives `None`
+/// unless it is positive.
+pub fn filter_by_name(name: &str, radius: Option<f64>) -> Option<Box<dyn Filter>> {
+    if let Some(radius) = radius {
+        if radius <= 0.0 || radius.is_nan() {
+            return None;
+        }
+    }
+
+    match name {
+        "box" => Some(Box::new(BoxFilter::new(
+            radius.unwrap_or(BoxFilter::DEFAULT_RADIUS),
+        ))),
+        "tent" => Some(Box::new(TentFilter::new(
+            radius.unwrap_or(TentFilter::DEFAULT_RADIUS),
+        ))),
+        "gaussian" => Some(Box::new(GaussianFilter::new(
+            radius.unwrap_or(GaussianFilter::DEFAULT_RADIUS),
+            GaussianFilter::DEFAULT_SIGMA,
+        ))),
+        "mitchell" => Some(Box::new(MitchellFilter::new(
+            radius.unwrap_or(MitchellFilter::DEFAULT_RADIUS),
+            MitchellFilter::DEFAULT_B,
+            MitchellFilter::DEFAULT_C,
+        ))),
+        "lanczos" => Some(Box::new(LanczosFilter::new(
+            radius.unwrap_or(LanczosFilter::DEFAULT_RADIUS),
+            LanczosFilter::DEFAULT_TAU,
+        ))),
+        _ => None,
+    }
+}
+
+/// Weights every sample within the radius equally. With the default radius of half a pixel,
+/// each pixel is the plain average of the samples inside it.
+#[derive(Debug, Clone, Copy)]
+pub struct BoxFilter {
+    radius: f64,
+}
+
+impl BoxFilter {
+    pub const DEFAULT_RADIUS: f64 = 0.5;
+
+    /// Panics unless `radius` is positive.
+    pub fn new(radius: f64) -> Self {
+        assert!(radius > 0.0, "BoxFilter radius must be positive");
+        Self { radius }
+    }
+}
+
+impl Default for BoxFilter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RADIUS)
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        if x.abs() <= self.radius && y.abs() <= self.radius {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A separable triangle filter, falling linearly from the center to zero at the radius.
+#[derive(Debug, Clone, Copy)]
+pub struct TentFilter {
+    radius: f64,
+}
+
+impl TentFilter {
+    pub const DEFAULT_RADIUS: f64 = 1.0;
+
+    /// Panics unless `radius` is positive.
+    pub fn new(radius: f64) -> Self {
+        assert!(radius > 0.0, "TentFilter radius must be positive");
+        Self { radius }
+    }
+}
+
+impl Default for TentFilter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RADIUS)
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        (self.radius - x.abs()).max(0.0) * (self.radius - y.abs()).max(0.0)
+    }
+}
+
+/// A separable Gaussian with standard deviation `sigma`, shifted down so it reaches zero at
+/// the radius.
+#[derive(Debug, Clone, Copy)]
+pub struct GaussianFilter {
+    radius: f64,
+    sigma: f64,
+    /// Value of the Gaussian at the radius, subtracted so the filter ends at zero.
+    edge: f64,
+}
+
+impl GaussianFilter {
+    pub const DEFAULT_RADIUS: f64 = 1.5;
+    pub const DEFAULT_SIGMA: f64 = 0.5;
+
+    /// Panics unless `radius` is positive.
+    pub fn new(radius: f64, sigma: f64) -> Self {
+        assert!(radius > 0.0, "GaussianFilter radius must be positive");
+        Self {
+            radius,
+            sigma,
+            edge: gaussian(radius, sigma),
+        }
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        (gaussian(x, self.sigma) - self.edge).max(0.0)
+    }
+}
+
+impl Default for GaussianFilter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RADIUS, Self::DEFAULT_SIGMA)
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+fn gaussian(x: f64, sigma: f64) -> f64 {
+    (-x * x / (2.0 * sigma * sigma)).exp()
+}
+
+/// The separable Mitchell-Netravali cubic, with the `b` and `c` parameters trading blur
+/// against ringing. The defaults of `b = c = 1/3` are the values Mitchell and Netravali
+/// recommend.
+#[derive(Debug, Clone, Copy)]
+pub struct MitchellFilter {
+    radius: f64,
+    b: f64,
+    c: f64,
+}
+
+impl MitchellFilter {
+    pub const DEFAULT_RADIUS: f64 = 2.0;
+    pub const DEFAULT_B: f64 = 1.0 / 3.0;
+    pub const DEFAULT_C: f64 = 1.0 / 3.0;
+
+    /// Panics unless `radius` is positive.
+    pub fn new(radius: f64, b: f64, c: f64) -> Self {
+        assert!(radius > 0.0, "MitchellFilter radius must be positive");
+        Self { radius, b, c }
+    }
+
+    /// The cubic over `[-2, 2]`, with `x` scaled from the filter's radius.
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = (2.0 * x / self.radius).abs();
+        let (b, c) = (self.b, self.c);
+        let weight = if x < 1.0 {
+            (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                + (6.0 - 2.0 * b)
+        } else if x < 2.0 {
+            (-b - 6.0 * c) * x * x * x
+                + (6.0 * b + 30.0 * c) * x * x
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c)
+        } else {
+            0.0
+        };
+        weight / 6.0
+    }
+}
+
+impl Default for MitchellFilter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RADIUS, Self::DEFAULT_B, Self::DEFAULT_C)
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+/// A separable sinc filter windowed by a wider sinc, `sinc(x) sinc(x / tau)`, cut off at the
+/// radius. Sharper than the other filters, at the cost of some ringing around edges.
+#[derive(Debug, Clone, Copy)]
+pub struct LanczosFilter {
+    radius: f64,
+    tau: f64,
+}
+
+impl LanczosFilter {
+    pub const DEFAULT_RADIUS: f64 = 3.0;
+    pub const DEFAULT_TAU: f64 = 3.0;
+
+    /// Panics unless `radius` is positive.
+    pub fn new(radius: f64, tau: f64) -> Self {
+        assert!(radius > 0.0, "LanczosFilter radius must be positive");
+        Self { radius, tau }
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        if x.abs() >= self.radius {
+            0.0
+        } else {
+            sinc(x) * sinc(x / self.tau)
+        }
+    }
+}
+
+impl Default for LanczosFilter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RADIUS, Self::DEFAULT_TAU)
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
@@ -0,0 +1,267 @@
+//! Linear HDR framebuffers produced by rendering a [`Scene`](crate::scene::Scene)
+
+mod filter;
+mod output;
+mod tonemap;
+
+pub use filter::*;
+pub use output::*;
+pub use tonemap::*;
+
+use crate::math::Color;
+use image::RgbImage;
+use std::path::Path;
+
+/// A linear, unclamped RGB framebuffer with per-pixel sample counts.
+///
+/// Pixels are stored row by row starting at the top-left of the image. Values are the
+/// filter-weighted mean radiance of the samples around each pixel and are not clamped or gamma
+/// corrected, so they can be tone mapped, composited or written to an HDR format before being
+/// quantized with [`Film::to_rgb8`].
+///
+/// Film coordinates run from `(0, 0)` at the top-left corner of the image to
+/// `(width, height)` at the bottom-right, so pixel `(x, y)` covers `[x, x + 1) × [y, y + 1)`.
+#[derive(Debug, Clone)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 3]>,
+    samples: Vec<u32>,
+    /// Filter-weighted sums of the samples, which `pixels` are normalized from.
+    sums: Vec<[f64; 3]>,
+    weights: Vec<f64>,
+}
+
+impl Film {
+    /// Creates a black film with no samples.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 3]; width * height],
+            samples: vec![0; width * height],
+            sums: vec![[0.0; 3]; width * height],
+            weights: vec![0.0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Mean radiance of the pixel at column `x` and row `y`, counted from the top.
+    pub fn pixel(&self, x: usize, y: usize) -> [f32; 3] {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn sample_count(&self, x: usize, y: usize) -> u32 {
+        self.samples[self.index(x, y)]
+    }
+
+    /// Sets a pixel to the mean `color` of `samples` samples. Non-finite channels are stored as
+    /// zero so that a single bad sample can't poison later processing.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color, samples: u32) {
+        let index = self.index(x, y);
+        self.sums[index] = finite(color);
+        self.weights[index] = 1.0;
+        self.samples[index] = samples;
+        self.resolve(index);
+    }
+
+    /// Adds one sample to a pixel's mean, with the weight of every other sample in it.
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Color) {
+        let index = self.index(x, y);
+        self.samples[index] += 1;
+        self.accumulate(index, finite(color), 1.0);
+    }
+
+    /// Adds a sample taken at film position `(x, y)` to every pixel within `filter`'s radius,
+    /// weighted by the filter, and counts it in the pixel it falls in. Non-finite channels are
+    /// treated as zero.
+    pub fn splat(&mut self, (x, y): (f64, f64), color: Color, filter: &dyn Filter) {
+        if x >= 0.0 && y >= 0.0 && x < self.width as f64 && y < self.height as f64 {
+            let index = self.index(x as usize, y as usize);
+            self.samples[index] += 1;
+        }
+
+        let color = finite(color);
+        let radius = filter.radius();
+        // pixels whose centers lie in (p - radius, p + radius], so that with a half pixel box
+        // filter every sample lands in exactly one pixel
+        let x0 = ((x - 0.5 - radius).floor() + 1.0).max(0.0) as usize;
+        let y0 = ((y - 0.5 - radius).floor() + 1.0).max(0.0) as usize;
+        let x1 = ((x - 0.5 + radius).floor() + 1.0).clamp(0.0, self.width as f64) as usize;
+        let y1 = ((y - 0.5 + radius).floor() + 1.0).clamp(0.0, self.height as f64) as usize;
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let weight = filter.evaluate(px as f64 + 0.5 - x, py as f64 + 0.5 - y);
+                if weight != 0.0 {
+                    let index = py * self.width + px;
+                    let weighted = [color[0] * weight, color[1] * weight, color[2] * weight];
+                    self.accumulate(index, weighted, weight);
+                }
+            }
+        }
+    }
+
+    /// Adds the samples of `other` to this film, with `other`'s top-left pixel placed at pixel
+    /// `(x, y)`. Pixels that fall outside this film are dropped.
+    ///
+    /// This lets parts of an image be rendered into separate films, for example on different
+    /// threads, and combined afterwards.
+    pub fn merge(&mut self, other: &Film, x: isize, y: isize) {
+        for other_y in 0..other.height {
+            let film_y = y + other_y as isize;
+            if film_y < 0 || film_y >= self.height as isize {
+                continue;
+            }
+            for other_x in 0..other.width {
+                let film_x = x + other_x as isize;
+                if film_x < 0 || film_x >= self.width as isize {
+                    continue;
+                }
+
+                let from = other.index(other_x, other_y);
+                let index = self.index(film_x as usize, film_y as usize);
+                self.samples[index] += other.samples[from];
+                self.accumulate(index, other.sums[from], other.weights[from]);
+            }
+        }
+    }
+
+    /// All pixels, row by row from the top of the image.
+    pub fn pixels(&self) -> &[[f32; 3]] {
+        &self.pixels
+    }
+
+    /// Sample counts in the same order as [`Film::pixels`].
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.samples
+    }
+
+    /// Quantizes the film to 8-bit sRGB, clipping each channel at white.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.to_rgb8_with(&Clamp, 0.0)
+    }
+
+    /// Quantizes the film to 8-bit sRGB, after scaling it by `2^exposure` and compressing it
+    /// with `tone_map`.
+    pub fn to_rgb8_with(&self, tone_map: &dyn ToneMap, exposure: f32) -> Vec<u8> {
+        let scale = exposure.exp2();
+        let mut rgb = Vec::with_capacity(self.pixels.len() * 3);
+        for pixel in self.pixels.iter() {
+            let mapped = tone_map.map([pixel[0] * scale, pixel[1] * scale, pixel[2] * scale]);
+            for &c in mapped.iter() {
+                rgb.push((255.0 * linear_to_srgb(c) + 0.5) as u8);
+            }
+        }
+        rgb
+    }
+
+    /// Visualizes the number of samples taken in each pixel, from black for no samples through
+    /// blue, red and yellow to white for the pixels with the most samples.
+    pub fn sample_heatmap(&self) -> RgbImage {
+        const RAMP: [[f32; 3]; 5] = [
+            [0.0, 0.0, 0.0],
+            [0.1, 0.1, 0.8],
+            [0.9, 0.1, 0.2],
+            [1.0, 0.8, 0.0],
+            [1.0, 1.0, 1.0],
+        ];
+
+        let max = self.samples.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let mut rgb = Vec::with_capacity(self.samples.len() * 3);
+        for &count in self.samples.iter() {
+            let t = count as f32 / max * (RAMP.len() - 1) as f32;
+            let stop = (t as usize).min(RAMP.len() - 2);
+            let f = t - stop as f32;
+            for (low, high) in RAMP[stop].iter().zip(RAMP[stop + 1].iter()) {
+                rgb.push((255.0 * (low + (high - low) * f) + 0.5) as u8);
+            }
+        }
+        RgbImage::from_raw(self.width as u32, self.height as u32, rgb).unwrap()
+    }
+
+    /// Converts the film to an 8-bit image with [`Film::to_rgb8`].
+    pub fn to_image(&self) -> RgbImage {
+        self.to_image_with(&Clamp, 0.0)
+    }
+
+    /// Converts the film to an 8-bit image with [`Film::to_rgb8_with`].
+    pub fn to_image_with(&self, tone_map: &dyn ToneMap, exposure: f32) -> RgbImage {
+        let rgb = self.to_rgb8_with(tone_map, exposure);
+        RgbImage::from_raw(self.width as u32, self.height as u32, rgb).unwrap()
+    }
+
+    /// Saves the film with the format chosen by the file extension.
+    ///
+    /// `.exr` files are written as half-float OpenEXR and `.hdr` files as Radiance RGBE, both
+    /// keeping the unclamped radiance. Any other extension supported by the `image` crate is
+    /// written as 8-bit sRGB with [`Film::to_rgb8`].
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        self.save_with(path, &Clamp, 0.0)
+    }
+
+    /// Saves the film like [`Film::save`], tone mapping 8-bit formats with `tone_map` after
+    /// scaling by `2^exposure`. HDR formats are always written with the film's own radiance.
+    pub fn save_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        tone_map: &dyn ToneMap,
+        exposure: f32,
+    ) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        match output::format(path) {
+            OutputFormat::Exr => self.save_exr(path, ExrOptions::default()),
+            OutputFormat::Hdr => self.save_hdr(path),
+            OutputFormat::Ldr => self
+                .to_image_with(tone_map, exposure)
+                .save(path)
+                .map_err(SaveError::Image),
+        }
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        y * self.width + x
+    }
+
+    fn accumulate(&mut self, index: usize, sum: [f64; 3], weight: f64) {
+        for (total, value) in self.sums[index].iter_mut().zip(sum.iter()) {
+            *total += value;
+        }
+        self.weights[index] += weight;
+        self.resolve(index);
+    }
+
+    /// Updates a pixel from its weighted sums. Filters with negative lobes can push pixels
+    /// below zero near sharp edges, so the result is clamped at black.
+    fn resolve(&mut self, index: usize) {
+        let weight = self.weights[index];
+        let pixel = &mut self.pixels[index];
+        for (value, sum) in pixel.iter_mut().zip(self.sums[index].iter()) {
+            *value = if weight != 0.0 {
+                (sum / weight).max(0.0) as f32
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
+/// A color's channels, with non-finite values replaced by zero so that a single bad sample
+/// can't poison later processing.
+fn finite(color: Color) -> [f64; 3] {
+    let mut rgb = [0.0; 3];
+    for (c, value) in rgb.iter_mut().enumerate() {
+        if color[c].is_finite() {
+            *value = color[c];
+        }
+    }
+    rgb
+}
@@ -0,0 +1,142 @@
+use super::Film;
+use exr::prelude::{f16, Image as ExrImage, IntoSample, SpecificChannels, WritableImage};
+use image::codecs::hdr::HdrEncoder;
+use image::{ImageError, Rgb};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+/// Errors that can occur while saving a [`Film`].
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Image(ImageError),
+    Exr(exr::error::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Image(e) => write!(f, "{}", e),
+            SaveError::Exr(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SaveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveError::Io(e) => Some(e),
+            SaveError::Image(e) => Some(e),
+            SaveError::Exr(e) => Some(e),
+        }
+    }
+}
+
+/// Sample type of the color channels in an OpenEXR file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExrPrecision {
+    /// 16-bit floats, the usual choice for color. Values above 65504 are saturated.
+    Half,
+    /// 32-bit floats.
+    Float,
+}
+
+/// Settings for [`Film::save_exr`].
+#[derive(Debug, Clone, Copy)]
+pub struct ExrOptions {
+    pub precision: ExrPrecision,
+    /// Also write each pixel's sample count as a `samples` channel.
+    pub sample_counts: bool,
+}
+
+impl Default for ExrOptions {
+    fn default() -> Self {
+        Self {
+            precision: ExrPrecision::Half,
+            sample_counts: false,
+        }
+    }
+}
+
+/// How a film is written, based on the output file's extension.
+pub(crate) enum OutputFormat {
+    Exr,
+    Hdr,
+    Ldr,
+}
+
+pub(crate) fn format(path: &Path) -> OutputFormat {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("exr") => OutputFormat::Exr,
+        Some("hdr") => OutputFormat::Hdr,
+        _ => OutputFormat::Ldr,
+    }
+}
+
+impl Film {
+    /// Saves the film's linear radiance as an OpenEXR file with `R`, `G` and `B` channels.
+    pub fn save_exr<P: AsRef<Path>>(&self, path: P, options: ExrOptions) -> Result<(), SaveError> {
+        match options.precision {
+            ExrPrecision::Half => self.write_exr(path.as_ref(), options, |c| {
+                f16::from_f32(c.min(f16::MAX.to_f32()))
+            }),
+            ExrPrecision::Float => self.write_exr(path.as_ref(), options, |c| c),
+        }
+    }
+
+    fn write_exr<S: IntoSample>(
+        &self,
+        path: &Path,
+        options: ExrOptions,
+        convert: impl Fn(f32) -> S + Sync,
+    ) -> Result<(), SaveError> {
+        let size = (self.width, self.height);
+        let color = |x: usize, y: usize| {
+            let pixel = self.pixel(x, y);
+            (convert(pixel[0]), convert(pixel[1]), convert(pixel[2]))
+        };
+
+        let result = if options.sample_counts {
+            let channels = SpecificChannels::build()
+                .with_channel("R")
+                .with_channel("G")
+                .with_channel("B")
+                .with_channel("samples")
+                .with_pixel_fn(|position| {
+                    let (r, g, b) = color(position.x(), position.y());
+                    (r, g, b, self.sample_count(position.x(), position.y()))
+                });
+            ExrImage::from_channels(size, channels)
+                .write()
+                .to_file(path)
+        } else {
+            let channels = SpecificChannels::rgb(|position: exr::math::Vec2<usize>| {
+                color(position.x(), position.y())
+            });
+            ExrImage::from_channels(size, channels)
+                .write()
+                .to_file(path)
+        };
+
+        result.map_err(SaveError::Exr)
+    }
+
+    /// Saves the film's linear radiance as a Radiance RGBE (`.hdr`) file.
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        let file = File::create(path).map_err(SaveError::Io)?;
+        let pixels: Vec<Rgb<f32>> = self.pixels.iter().map(|&pixel| Rgb(pixel)).collect();
+
+        HdrEncoder::new(BufWriter::new(file))
+            .encode(&pixels, self.width, self.height)
+            .map_err(SaveError::Image)
+    }
+}
@@ -0,0 +1,168 @@
+//! Tone mapping operators for converting linear radiance to displayable values
+
+use crate::math::Color;
+
+/// Compresses linear, exposure-adjusted radiance into the `[0, 1]` display range.
+///
+/// Operators work on linear values; the sRGB transfer curve is applied afterwards by
+/// [`Film::to_rgb8_with`](super::Film::to_rgb8_with).
+pub trait ToneMap: Send + Sync {
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3];
+}
+
+/// Names accepted by [`tone_map_by_name`].
+pub const TONE_MAP_NAMES: &[&str] = &["clamp", "reinhard", "reinhard-extended", "aces", "hable"];
+
+/// Looks up an operator by name. `white` overrides the default white point of the operators
+/// that have one.
+pub fn tone_map_by_name(name: &str, white: Option<f32>) -> Option<Box<dyn ToneMap>> {
+    match name {
+        "clamp" => Some(Box::new(Clamp)),
+        "reinhard" => Some(Box::new(Reinhard)),
+        "reinhard-extended" => Some(Box::new(ExtendedReinhard::new(
+            white.unwrap_or(ExtendedReinhard::DEFAULT_WHITE),
+        ))),
+        "aces" => Some(Box::new(Aces)),
+        "hable" => Some(Box::new(Hable::new(white.unwrap_or(Hable::DEFAULT_WHITE)))),
+        _ => None,
+    }
+}
+
+/// Clips each channel to `[0, 1]`, leaving everything below white untouched.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Clamp;
+
+impl ToneMap for Clamp {
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3] {
+        [
+            rgb[0].clamp(0.0, 1.0),
+            rgb[1].clamp(0.0, 1.0),
+            rgb[2].clamp(0.0, 1.0),
+        ]
+    }
+}
+
+/// Reinhard's global operator `L / (1 + L)`, applied to luminance so hues are preserved.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reinhard;
+
+impl ToneMap for Reinhard {
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3] {
+        scale_luminance(rgb, |l| l / (1.0 + l))
+    }
+}
+
+/// Reinhard's operator with a white point: luminance `white` and above maps to 1.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtendedReinhard {
+    white: f32,
+}
+
+impl ExtendedReinhard {
+    pub const DEFAULT_WHITE: f32 = 4.0;
+
+    pub fn new(white: f32) -> Self {
+        Self { white }
+    }
+}
+
+impl Default for ExtendedReinhard {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WHITE)
+    }
+}
+
+impl ToneMap for ExtendedReinhard {
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let white_squared = self.white * self.white;
+        scale_luminance(rgb, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+    }
+}
+
+/// Krzysztof Narkowicz's fit of the ACES filmic reference rendering transform.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aces;
+
+impl ToneMap for Aces {
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let curve = |x: f32| {
+            let x = x.max(0.0) * 0.6;
+            ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+        };
+        [curve(rgb[0]), curve(rgb[1]), curve(rgb[2])]
+    }
+}
+
+/// John Hable's filmic curve from Uncharted 2, normalized so that `white` maps to 1.
+#[derive(Debug, Clone, Copy)]
+pub struct Hable {
+    white_scale: f32,
+}
+
+impl Hable {
+    /// Scene radiance is doubled before the curve, as in the original presentation.
+    const EXPOSURE_BIAS: f32 = 2.0;
+
+    pub const DEFAULT_WHITE: f32 = 11.2;
+
+    pub fn new(white: f32) -> Self {
+        Self {
+            white_scale: 1.0 / Self::curve(white),
+        }
+    }
+
+    fn curve(x: f32) -> f32 {
+        let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+        ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+    }
+}
+
+impl Default for Hable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WHITE)
+    }
+}
+
+impl ToneMap for Hable {
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let map = |x: f32| {
+            (Self::curve(x.max(0.0) * Self::EXPOSURE_BIAS) * self.white_scale).clamp(0.0, 1.0)
+        };
+        [map(rgb[0]), map(rgb[1]), map(rgb[2])]
+    }
+}
+
+/// Rec. 709 relative luminance of a linear color.
+pub fn luminance(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/// Like [`luminance`], for a [`Color`] as used while rendering.
+pub fn color_luminance(color: Color) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+/// The sRGB transfer function, encoding a linear value in `[0, 1]` for display.
+pub fn linear_to_srgb(x: f32) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+    if x <= 0.003_130_8 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Maps the luminance of `rgb` with `curve` and scales the color to match, clipping the result.
+fn scale_luminance(rgb: [f32; 3], curve: impl Fn(f32) -> f32) -> [f32; 3] {
+    let l = luminance(rgb);
+    if l <= 0.0 {
+        return [0.0; 3];
+    }
+
+    let scale = curve(l) / l;
+    [
+        (rgb[0] * scale).clamp(0.0, 1.0),
+        (rgb[1] * scale).clamp(0.0, 1.0),
+        (rgb[2] * scale).clamp(0.0, 1.0),
+    ]
+}
@@ -1,16 +1,25 @@
 //! Scene-level abstractions for objects that can intersect with raycasts
 
 use crate::bvh::BvhNode;
+use crate::light::LightList;
 use crate::material::Material;
-use crate::math::{Aabb, Point3, Ray, Vec3};
-use rand::{thread_rng, Rng};
+use crate::math::{Aabb, Mat4, Point3, Ray, Vec3};
+use crate::sampler::Sampler;
 use std::sync::Arc;
 
 /// Maintains a record of a ray intersection with a [`Hittable`] object.
 #[derive(Clone)]
 pub struct HitRecord {
     pub point: Point3,
+    /// Normal used for shading, facing the side the ray came from.
     pub normal: Vec3,
+    /// Normal of the surface itself, on the same side as `normal`. The two differ where
+    /// shading normals are interpolated across a mesh.
+    pub geometric_normal: Vec3,
+    /// Direction of increasing `u` along the surface (dp/du), which orients anisotropic
+    /// materials. Not necessarily unit length or perpendicular to `normal`, and zero where the
+    /// surface has no such direction.
+    pub tangent: Vec3,
     pub t: f64,
     pub u: f64,
     pub v: f64,
@@ -26,6 +35,7 @@ impl HitRecord {
         } else {
             -(*outward_normal)
         };
+        self.geometric_normal = self.normal;
     }
 }
 
@@ -34,6 +44,8 @@ impl Default for HitRecord {
         Self {
             point: Point3::default(),
             normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            tangent: Vec3::default(),
             t: 0.,
             u: 0.,
             v: 0.,
@@ -53,9 +65,23 @@ pub trait Hittable: Sync + Send {
         0.0
     }
 
-    fn random(&self, o: &Vec3) -> Vec3 {
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         Vec3::new(1.0, 0.0, 0.0)
     }
+
+    /// Adds this object's emissive surfaces to `lights`. `this` is the pointer the object is
+    /// shared through, so surfaces can add themselves, and `material` overrides the material of
+    /// every surface, as for an [`Instance`].
+    ///
+    /// Only surfaces that implement [`Hittable::pdf_value`] and [`Hittable::random`] can be
+    /// sampled as lights; light from the rest only reaches paths that happen to hit them.
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+    }
 }
 /// Stores a list of hittable scene objects.
 pub struct HittableList {
@@ -73,6 +99,11 @@ impl HittableList {
         self.objects.push(Arc::new(object));
     }
 
+    /// Adds an object that may also be referenced elsewhere in the scene.
+    pub fn add_shared(&mut self, object: Arc<dyn Hittable>) {
+        self.objects.push(object);
+    }
+
     pub fn clear(&mut self) {
         self.objects.clear()
     }
@@ -130,6 +161,10 @@ impl Hittable for HittableList {
     }
 
     fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
         let weight = 1.0 / self.objects.len() as f64;
         let mut sum = 0.0;
 
@@ -140,133 +175,268 @@ impl Hittable for HittableList {
         sum
     }
 
-    fn random(&self, o: &Vec3) -> Vec3 {
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         let size = self.objects.len();
-        self.objects[thread_rng().gen_range(0, size)].random(o)
+        if size == 0 {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+        let index = ((sampler.get_1d() * size as f64) as usize).min(size - 1);
+        self.objects[index].random(o, sampler)
+    }
+
+    fn add_lights(
+        &self,
+        _this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        for object in self.objects.iter() {
+            object.add_lights(object, material, lights);
+        }
     }
 }
 
-pub struct Translate {
+/// Places a shared object in the scene with an affine transform.
+///
+/// Rays are moved into the object's space by the inverse matrix, and hits are moved back out,
+/// with normals transformed by the inverse-transpose. Transforms compose without nesting, so
+/// `Transform::new(obj, Mat4::rotation_y(15.0)).translate(offset)` rotates and then translates.
+pub struct Transform {
     hittable: Arc<dyn Hittable>,
-    offset: Vec3,
+    matrix: Mat4,
+    inverse: Mat4,
+    normal_matrix: Mat4,
 }
 
-impl Translate {
-    pub fn new(hittable: impl Hittable + 'static, offset: Vec3) -> Self {
+impl Transform {
+    /// Panics if `matrix` is not invertible.
+    pub fn new(hittable: Arc<dyn Hittable>, matrix: Mat4) -> Self {
+        let inverse = matrix
+            .inverse()
+            .expect("Transform matrix must be invertible");
+
         Self {
-            hittable: Arc::new(hittable),
-            offset,
+            hittable,
+            matrix,
+            inverse,
+            normal_matrix: inverse.transpose(),
         }
     }
-}
 
-impl Hittable for Translate {
-    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
-        if let Some(aabb) = self.hittable.bounding_box(t0, t1) {
-            Some(Aabb::new(
-                aabb.min() + self.offset,
-                aabb.max() + self.offset,
-            ))
-        } else {
-            None
-        }
+    /// Applies `matrix` after the current transform.
+    pub fn then(self, matrix: Mat4) -> Self {
+        Self::new(self.hittable, matrix * self.matrix)
     }
 
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let moved_ray = Ray::new(ray.origin() - self.offset, ray.direction(), ray.time());
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.then(Mat4::translation(offset))
+    }
+
+    pub fn scale(self, scale: Vec3) -> Self {
+        self.then(Mat4::scaling(scale))
+    }
+
+    /// Rotates by `angle` degrees about `axis`.
+    pub fn rotate(self, axis: Vec3, angle: f64) -> Self {
+        self.then(Mat4::rotation(axis, angle))
+    }
+
+    pub fn rotate_x(self, angle: f64) -> Self {
+        self.then(Mat4::rotation_x(angle))
+    }
+
+    pub fn rotate_y(self, angle: f64) -> Self {
+        self.then(Mat4::rotation_y(angle))
+    }
+
+    pub fn rotate_z(self, angle: f64) -> Self {
+        self.then(Mat4::rotation_z(angle))
+    }
+
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
 
-        if let Some(mut hit_rec) = self.hittable.hit(&moved_ray, t_min, t_max) {
-            hit_rec.point += self.offset;
-            let outward_normal = hit_rec.normal;
-            hit_rec.set_face_normal(&moved_ray, &outward_normal);
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.hittable
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // The direction is not renormalized, so t is the same in both spaces
+        let object_ray = Ray::new(
+            self.inverse.transform_point(&ray.origin()),
+            self.inverse.transform_vector(&ray.direction()),
+            ray.time(),
+        );
+
+        if let Some(mut hit_rec) = self.hittable.hit(&object_ray, t_min, t_max) {
+            // The inverse-transpose preserves the sign of dot(normal, direction), so the
+            // object-space front_face still holds
+            hit_rec.point = self.matrix.transform_point(&hit_rec.point);
+            hit_rec.normal = self.normal_matrix.transform_vector(&hit_rec.normal).unit();
+            hit_rec.geometric_normal = self
+                .normal_matrix
+                .transform_vector(&hit_rec.geometric_normal)
+                .unit();
+            hit_rec.tangent = self.matrix.transform_vector(&hit_rec.tangent);
             Some(hit_rec)
         } else {
             None
         }
     }
-}
 
-pub struct RotateY {
-    hittable: Arc<dyn Hittable>,
-    sin_theta: f64,
-    cos_theta: f64,
-    aabb: Option<Aabb>,
-}
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        let aabb = self.hittable.bounding_box(t0, t1)?;
+
+        let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
 
-impl RotateY {
-    pub fn new(hittable: impl Hittable + 'static, angle: f64) -> Self {
-        let radians = angle.to_radians();
-        let sin_theta = radians.sin();
-        let cos_theta = radians.cos();
-        let aabb = hittable.bounding_box(0.0, 1.0).unwrap();
-
-        use std::f64::{INFINITY, NEG_INFINITY};
-        let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
-        let mut max = Point3::new(NEG_INFINITY, NEG_INFINITY, NEG_INFINITY);
-
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    let x = i as f64 * aabb.max().x() + (1.0 - i as f64) * aabb.min().x();
-                    let y = j as f64 * aabb.max().y() + (1.0 - j as f64) * aabb.min().y();
-                    let z = k as f64 * aabb.max().z() + (1.0 - k as f64) * aabb.min().z();
-
-                    let newx = cos_theta * x + sin_theta * z;
-                    let newz = -sin_theta * x + cos_theta * z;
-
-                    let tester = Vec3::new(newx, y, newz);
-
-                    for c in 0..3 {
-                        min[c] = min[c].min(tester[c]);
-                        max[c] = max[c].max(tester[c]);
-                    }
-                }
+        for i in 0..8 {
+            let corner = Point3::new(
+                if i & 1 == 0 {
+                    aabb.min().x()
+                } else {
+                    aabb.max().x()
+                },
+                if i & 2 == 0 {
+                    aabb.min().y()
+                } else {
+                    aabb.max().y()
+                },
+                if i & 4 == 0 {
+                    aabb.min().z()
+                } else {
+                    aabb.max().z()
+                },
+            );
+            let p = self.matrix.transform_point(&corner);
+
+            for c in 0..3 {
+                min[c] = min[c].min(p[c]);
+                max[c] = max[c].max(p[c]);
             }
         }
 
-        Self {
-            hittable: Arc::new(hittable),
-            cos_theta,
-            sin_theta,
-            aabb: Some(Aabb::new(min, max)),
+        Some(Aabb::new(min, max))
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        // Convert the object-space solid angle density to world space. For a unit direction
+        // w mapped to A*w by the inverse's linear part A, dw_object/dw_world = |det A|/|A*w|^3.
+        let direction = v.unit();
+        let object_direction = self.inverse.transform_vector(&direction);
+        let object_origin = self.inverse.transform_point(o);
+        let stretch = object_direction.length();
+
+        self.hittable.pdf_value(&object_origin, &object_direction)
+            * self.inverse.determinant3().abs()
+            / (stretch * stretch * stretch)
+    }
+
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let object_origin = self.inverse.transform_point(o);
+        self.matrix
+            .transform_vector(&self.hittable.random(&object_origin, sampler))
+    }
+
+    fn add_lights(
+        &self,
+        _this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        let mut object_lights = LightList::new();
+        self.hittable
+            .add_lights(&self.hittable, material, &mut object_lights);
+
+        // exact for uniform scales, and close enough to weight lights by for the rest
+        let area_scale = self.matrix.determinant3().abs().powf(2.0 / 3.0);
+        for (light, power) in object_lights.iter() {
+            lights.add(
+                Arc::new(Transform::new(light.clone(), self.matrix)),
+                power * area_scale,
+            );
         }
     }
 }
 
-impl Hittable for RotateY {
-    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
-        self.aabb
+/// A placement of shared geometry with its own transform and optional material.
+///
+/// Many instances can reference the same object (typically a prebuilt [`BvhNode`] or
+/// [`TriangleMesh`](crate::primitives::TriangleMesh)), so the geometry is only stored once.
+pub struct Instance {
+    transform: Transform,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    /// Panics if `matrix` is not invertible.
+    pub fn new(object: Arc<dyn Hittable>, matrix: Mat4) -> Self {
+        Self {
+            transform: Transform::new(object, matrix),
+            material: None,
+        }
+    }
+
+    /// Replaces the material of every surface hit through this instance.
+    pub fn with_material(mut self, material: Arc<dyn Material>) -> Self {
+        self.material = Some(material);
+        self
     }
 
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let mut origin = ray.origin();
-        let mut direction = ray.direction();
+    pub fn matrix(&self) -> &Mat4 {
+        self.transform.matrix()
+    }
 
-        origin[0] = self.cos_theta * ray.origin()[0] - self.sin_theta * ray.origin()[2];
-        origin[2] = self.sin_theta * ray.origin()[0] + self.cos_theta * ray.origin()[2];
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        self.transform.object()
+    }
 
-        direction[0] = self.cos_theta * ray.direction()[0] - self.sin_theta * ray.direction()[2];
-        direction[2] = self.sin_theta * ray.direction()[0] + self.cos_theta * ray.direction()[2];
+    pub fn material(&self) -> Option<&Arc<dyn Material>> {
+        self.material.as_ref()
+    }
 
-        let rotated_ray = Ray::new(origin, direction, ray.time());
+    /// Creates a copy of this instance, sharing its object and material, at a new placement.
+    pub fn with_matrix(&self, matrix: Mat4) -> Self {
+        Self {
+            transform: Transform::new(self.object().clone(), matrix),
+            material: self.material.clone(),
+        }
+    }
+}
 
-        if let Some(mut hit_rec) = self.hittable.hit(&rotated_ray, t_min, t_max) {
-            let mut p = hit_rec.point;
-            let mut n = hit_rec.normal;
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut hit_rec = self.transform.hit(ray, t_min, t_max)?;
+        if self.material.is_some() {
+            hit_rec.material = self.material.clone();
+        }
+        Some(hit_rec)
+    }
 
-            p[0] = self.cos_theta * hit_rec.point[0] + self.sin_theta * hit_rec.point[2];
-            p[2] = -self.sin_theta * hit_rec.point[0] + self.cos_theta * hit_rec.point[2];
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.transform.bounding_box(t0, t1)
+    }
 
-            n[0] = self.cos_theta * hit_rec.normal[0] + self.sin_theta * hit_rec.normal[2];
-            n[2] = -self.sin_theta * hit_rec.normal[0] + self.cos_theta * hit_rec.normal[2];
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        self.transform.pdf_value(o, v)
+    }
 
-            hit_rec.point = p;
-            hit_rec.set_face_normal(&rotated_ray, &n);
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.transform.random(o, sampler)
+    }
 
-            Some(hit_rec)
-        } else {
-            None
-        }
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        // an enclosing instance's material is applied to hits last, so it takes precedence
+        let material = material.or(self.material.as_ref());
+        self.transform.add_lights(this, material, lights);
     }
 }
 
@@ -292,4 +462,14 @@ impl Hittable for FlipFace {
     fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
         self.hittable.bounding_box(t0, t1)
     }
+
+    fn add_lights(
+        &self,
+        _this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        // light sampling doesn't depend on which way the surface faces
+        self.hittable.add_lights(&self.hittable, material, lights);
+    }
 }
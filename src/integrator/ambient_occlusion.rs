@@ -0,0 +1,49 @@
+use super::Integrator;
+use crate::hittable::Hittable;
+use crate::math::{Color, Onb, Ray, Vec3};
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+/// Ambient occlusion: white where a cosine-distributed ray from the first hit escapes, black
+/// where it hits something closer than `distance`. Rays that miss everything are white.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusionIntegrator {
+    distance: f64,
+}
+
+impl AmbientOcclusionIntegrator {
+    /// Creates an integrator that counts hits closer than `distance` as occluding.
+    pub fn new(distance: f64) -> Self {
+        Self { distance }
+    }
+}
+
+impl Default for AmbientOcclusionIntegrator {
+    fn default() -> Self {
+        Self::new(f64::INFINITY)
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        _max_depth: u64,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let hit_rec = match scene.hittables().hit(&ray, 0.001, f64::INFINITY) {
+            Some(hit_rec) => hit_rec,
+            None => return Color::new(1.0, 1.0, 1.0),
+        };
+
+        let uvw = Onb::new(&hit_rec.normal);
+        let direction = uvw.local_vec(&Vec3::cosine_direction(sampler.get_2d()));
+        let occlusion_ray = Ray::new(hit_rec.point, direction, ray.time());
+        // the direction is a unit vector, so `t` is the distance to the occluder
+        match scene.hittables().hit(&occlusion_ray, 0.001, self.distance) {
+            Some(_) => Color::default(),
+            None => Color::new(1.0, 1.0, 1.0),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Light transport algorithms that estimate the radiance arriving along camera rays.
+//!
+//! [`Scene::render`](crate::scene::Scene::render) handles pixels, samples and the film, and
+//! hands each camera ray to the scene's [`Integrator`] to decide what color it sees.
+
+mod ambient_occlusion;
+mod normals;
+mod path;
+
+pub use ambient_occlusion::*;
+pub use normals::*;
+pub use path::*;
+
+use crate::math::{Color, Ray};
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+/// Estimates the radiance arriving at the camera along a ray.
+pub trait Integrator: Send + Sync {
+    /// Returns an estimate of the light arriving along `ray`, following at most `max_depth`
+    /// bounces. Every random decision should be drawn from `sampler`.
+    fn radiance(&self, scene: &Scene, ray: Ray, max_depth: u64, sampler: &mut dyn Sampler)
+        -> Color;
+}
+
+/// Names accepted by [`integrator_by_name`].
+pub const INTEGRATOR_NAMES: &[&str] = &["path", "ao", "normals"];
+
+/// Looks up an integrator by name, with its default settings.
+pub fn integrator_by_name(name: &str) -> Option<Box<dyn Integrator>> {
+    match name {
+        "path" => Some(Box::new(PathIntegrator::default())),
+        "ao" => Some(Box::new(AmbientOcclusionIntegrator::default())),
+        "normals" => Some(Box::new(NormalsIntegrator)),
+        _ => None,
+    }
+}
@@ -0,0 +1,25 @@
+use super::Integrator;
+use crate::hittable::Hittable;
+use crate::math::{Color, Ray};
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+/// Shows the surface normal at the first hit, mapped from `[-1, 1]` to `[0, 1]` per axis.
+/// Rays that miss everything are black.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalsIntegrator;
+
+impl Integrator for NormalsIntegrator {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        _max_depth: u64,
+        _sampler: &mut dyn Sampler,
+    ) -> Color {
+        match scene.hittables().hit(&ray, 0.001, f64::INFINITY) {
+            Some(hit_rec) => 0.5 * (hit_rec.normal.unit() + Color::new(1.0, 1.0, 1.0)),
+            None => Color::default(),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use super::Integrator;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::{Material, ScatterRecord};
+use crate::math::{Color, Ray, Vec3};
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+/// How the light sampling and BSDF sampling estimates of the same light are weighted against
+/// each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MisHeuristic {
+    /// Weights each strategy in proportion to its density.
+    Balance,
+    /// Weights each strategy in proportion to its squared density, which leans harder on
+    /// whichever strategy is much more likely to find the light.
+    Power,
+}
+
+impl MisHeuristic {
+    /// Weight of a sample drawn with density `pdf`, when the other strategy would have drawn
+    /// the same direction with density `other_pdf`.
+    pub fn weight(self, pdf: f64, other_pdf: f64) -> f64 {
+        let (pdf, other_pdf) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+        if pdf + other_pdf > 0.0 {
+            pdf / (pdf + other_pdf)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A unidirectional path tracer with next event estimation.
+///
+/// At every diffuse bounce a point on the scene's lights is sampled and tested with a shadow
+/// ray, and the path continues in a direction sampled from the material. Light reached both
+/// ways is combined with multiple importance sampling, so small bright lights are found by
+/// light sampling and large or glossy ones by BSDF sampling. Point, spot and directional
+/// lights can only be found by sampling them, so each gets a shadow ray at every bounce. An
+/// environment map is sampled as one more light, weighted against rays that escape the scene.
+/// Scenes without lights fall back to BSDF sampling alone.
+///
+/// After `roulette_depth` bounces, paths are randomly terminated with a probability that
+/// grows as their throughput falls, and the survivors are weighted up to compensate.
+#[derive(Debug, Clone, Copy)]
+pub struct PathIntegrator {
+    heuristic: MisHeuristic,
+    roulette_depth: u64,
+}
+
+impl PathIntegrator {
+    pub const DEFAULT_ROULETTE_DEPTH: u64 = 3;
+
+    /// Creates a path tracer that starts Russian roulette after `roulette_depth` bounces. Use
+    /// `u64::MAX` to always trace paths to the full depth.
+    pub fn new(heuristic: MisHeuristic, roulette_depth: u64) -> Self {
+        Self {
+            heuristic,
+            roulette_depth,
+        }
+    }
+
+    /// Estimates the light arriving at `hit_rec` directly from the scene's emissive surfaces,
+    /// by sampling a direction towards them and tracing a shadow ray. The result still has to
+    /// be scaled by the material's attenuation.
+    fn sample_light(
+        &self,
+        scene: &Scene,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        material: &dyn Material,
+        scatter_rec: &ScatterRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let lights = match scene.lights() {
+            Some(lights) => lights,
+            None => return Color::default(),
+        };
+
+        let direction = lights.random(&hit_rec.point, sampler);
+        let light_pdf = lights.pdf_value(&hit_rec.point, &direction);
+        if light_pdf <= 0.0 {
+            return Color::default();
+        }
+
+        let shadow_ray = Ray::new(hit_rec.point, direction, ray_in.time());
+        let scattering = scatter_rec.scattering(material, ray_in, hit_rec, &shadow_ray);
+        if scattering.max_component() <= 0.0 {
+            return Color::default();
+        }
+
+        // the light is only visible if nothing else is hit before it
+        let light_rec = match scene.hittables().hit(&shadow_ray, 0.001, f64::INFINITY) {
+            Some(light_rec) if is_light_surface(lights, &shadow_ray, light_rec.t) => light_rec,
+            _ => return Color::default(),
+        };
+        let emitted = match light_rec.material {
+            Some(ref light_material) => light_material.emitted(
+                &shadow_ray,
+                &light_rec,
+                light_rec.u,
+                light_rec.v,
+                &light_rec.point,
+            ),
+            None => return Color::default(),
+        };
+
+        let weight = self.heuristic.weight(
+            light_pdf,
+            scatter_pdf_value(scatter_rec, &shadow_ray.direction()),
+        );
+        emitted * scattering * weight / light_pdf
+    }
+
+    /// Estimates the light arriving at `hit_rec` directly from the scene's environment, by
+    /// sampling a direction towards it and tracing a shadow ray that has to leave the scene.
+    /// The result still has to be scaled by the material's attenuation.
+    fn sample_environment(
+        &self,
+        scene: &Scene,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        material: &dyn Material,
+        scatter_rec: &ScatterRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let environment = scene.environment();
+        let (direction, environment_pdf) = match environment.sample(sampler) {
+            Some(sample) => sample,
+            None => return Color::default(),
+        };
+        if environment_pdf <= 0.0 {
+            return Color::default();
+        }
+
+        let shadow_ray = Ray::new(hit_rec.point, direction, ray_in.time());
+        let scattering = scatter_rec.scattering(material, ray_in, hit_rec, &shadow_ray);
+        if scattering.max_component() <= 0.0 {
+            return Color::default();
+        }
+        if scene
+            .hittables()
+            .hit(&shadow_ray, 0.001, f64::INFINITY)
+            .is_some()
+        {
+            return Color::default();
+        }
+
+        let weight = self
+            .heuristic
+            .weight(environment_pdf, scatter_pdf_value(scatter_rec, &direction));
+        environment.radiance(&direction) * scattering * weight / environment_pdf
+    }
+}
+
+impl Default for PathIntegrator {
+    fn default() -> Self {
+        Self::new(MisHeuristic::Power, Self::DEFAULT_ROULETTE_DEPTH)
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        mut ray: Ray,
+        max_depth: u64,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let mut radiance = Color::default();
+        // fraction of the light at the current vertex that reaches the camera
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // density the previous bounce sampled `ray` with, or `None` for camera rays and
+        // specular bounces, which light sampling can't reproduce
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for bounce in 0..max_depth {
+            let hit_rec = match scene.hittables().hit(&ray, 0.001, f64::INFINITY) {
+                Some(hit_rec) => hit_rec,
+                None => {
+                    let direction = ray.direction().unit();
+                    let mut background = scene.environment().radiance(&direction);
+                    if let Some(bsdf_pdf) = bsdf_pdf {
+                        // the previous bounce could also have found this by sampling the
+                        // environment
+                        let environment_pdf = scene.environment().pdf(&direction);
+                        background *= self.heuristic.weight(bsdf_pdf, environment_pdf);
+                    }
+                    radiance += throughput * background;
+                    break;
+                }
+            };
+            let material = match hit_rec.material {
+                Some(ref material) => material.as_ref(),
+                None => {
+                    // no material - use red to stand out
+                    radiance += throughput * Color::new(1.0, 0.0, 0.0);
+                    break;
+                }
+            };
+
+            let mut emitted =
+                material.emitted(&ray, &hit_rec, hit_rec.u, hit_rec.v, &hit_rec.point);
+            if let (Some(bsdf_pdf), Some(lights)) = (bsdf_pdf, scene.lights()) {
+                // the previous bounce could also have found this light by sampling it
+                if is_light_surface(lights, &ray, hit_rec.t) {
+                    let light_pdf = lights.pdf_value(&ray.origin(), &ray.direction());
+                    emitted *= self.heuristic.weight(bsdf_pdf, light_pdf);
+                }
+            }
+            radiance += throughput * emitted;
+
+            let mut scatter_rec = ScatterRecord::new();
+            if !material.scatter(&ray, &hit_rec, &mut scatter_rec, sampler) {
+                break;
+            }
+
+            if let Some(specular_ray) = scatter_rec.specular_ray {
+                throughput = throughput * scatter_rec.attenuation;
+                ray = specular_ray;
+                bsdf_pdf = None;
+            } else {
+                let scatter_pdf = scatter_rec.pdf.clone().unwrap();
+
+                // light found by the shadow ray is one bounce further along the path
+                if bounce + 1 < max_depth {
+                    let direct =
+                        self.sample_light(scene, &ray, &hit_rec, material, &scatter_rec, sampler)
+                            + self.sample_environment(
+                                scene,
+                                &ray,
+                                &hit_rec,
+                                material,
+                                &scatter_rec,
+                                sampler,
+                            )
+                            + sample_light_sources(
+                                scene,
+                                &ray,
+                                &hit_rec,
+                                material,
+                                &scatter_rec,
+                                sampler,
+                            );
+                    radiance += throughput * scatter_rec.attenuation * direct;
+                }
+
+                let scattered = Ray::new(hit_rec.point, scatter_pdf.generate(sampler), ray.time());
+                let pdf = scatter_pdf.value(&scattered.direction());
+                if pdf <= 0.0 {
+                    break;
+                }
+
+                throughput = throughput
+                    * scatter_rec.attenuation
+                    * scatter_rec.scattering(material, &ray, &hit_rec, &scattered)
+                    / pdf;
+                ray = scattered;
+                bsdf_pdf = Some(pdf);
+            }
+
+            if bounce + 1 >= self.roulette_depth {
+                let survival = throughput.max_component().min(1.0);
+                if survival <= 0.0 || sampler.get_1d() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+        }
+
+        radiance
+    }
+}
+
+/// Sums the light arriving at `hit_rec` from each of the scene's point, spot and directional
+/// lights, tested with a shadow ray. BSDF sampling never finds these lights, so their samples
+/// need no MIS weights. The result still has to be scaled by the material's attenuation.
+fn sample_light_sources(
+    scene: &Scene,
+    ray_in: &Ray,
+    hit_rec: &HitRecord,
+    material: &dyn Material,
+    scatter_rec: &ScatterRecord,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    let mut radiance = Color::default();
+
+    for light in scene.light_sources().iter() {
+        let sample = match light.sample(&hit_rec.point, sampler) {
+            Some(sample) => sample,
+            None => continue,
+        };
+
+        let shadow_ray = Ray::new(hit_rec.point, sample.direction, ray_in.time());
+        let scattering = scatter_rec.scattering(material, ray_in, hit_rec, &shadow_ray);
+        if scattering.max_component() <= 0.0 {
+            continue;
+        }
+
+        // stop just short of the light, so a light placed on a surface isn't shadowed by it
+        let t_max = sample.distance * (1.0 - 1e-6);
+        if scene.hittables().hit(&shadow_ray, 0.001, t_max).is_none() {
+            radiance += sample.radiance * scattering;
+        }
+    }
+
+    radiance
+}
+
+/// Density the material's sampling strategy in `scatter_rec` gives `direction`.
+fn scatter_pdf_value(scatter_rec: &ScatterRecord, direction: &Vec3) -> f64 {
+    match scatter_rec.pdf {
+        Some(ref pdf) => pdf.value(direction),
+        None => 0.0,
+    }
+}
+
+/// Whether the first of the scene's lights along `ray` is the surface the scene hit at `t`,
+/// meaning light sampling could have chosen that surface.
+fn is_light_surface(lights: &dyn Hittable, ray: &Ray, t: f64) -> bool {
+    match lights.hit(ray, 0.001, f64::INFINITY) {
+        Some(light_rec) => (light_rec.t - t).abs() <= 1e-6 * t.abs().max(1.0),
+        None => false,
+    }
+}
@@ -1,9 +1,14 @@
 pub mod bvh;
 pub mod camera;
+pub mod film;
 pub mod hittable;
+pub mod integrator;
+pub mod light;
+pub mod loader;
 pub mod material;
 pub mod math;
 pub mod pdf;
 pub mod primitives;
+pub mod sampler;
 pub mod scene;
 pub mod texture;
@@ -0,0 +1,50 @@
+use super::{Light, LightSample};
+use crate::math::{Color, Onb, Point3, Vec3};
+use crate::sampler::Sampler;
+
+/// A distant light, such as the sun, arriving from around a single direction.
+///
+/// With an angular diameter of zero every shadow is perfectly sharp. A larger diameter treats
+/// the light as a disk of that apparent size, sampled uniformly, giving soft shadow edges.
+pub struct DirectionalLight {
+    frame: Onb,
+    irradiance: Color,
+    cos_radius: f64,
+}
+
+impl DirectionalLight {
+    /// Creates a light arriving from `direction` (pointing towards the light), with
+    /// `irradiance` on a surface facing it. `angular_diameter` is in degrees.
+    ///
+    /// Panics if `direction` is zero.
+    pub fn new(direction: Vec3, irradiance: Color, angular_diameter: f64) -> Self {
+        assert!(
+            !direction.near_zero(),
+            "DirectionalLight direction must not be zero"
+        );
+        Self {
+            frame: Onb::new(&direction),
+            irradiance,
+            cos_radius: (angular_diameter / 2.0).to_radians().cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let direction = if self.cos_radius < 1.0 {
+            self.frame
+                .local_vec(&Vec3::in_cone(self.cos_radius, sampler.get_2d()))
+        } else {
+            self.frame.w()
+        };
+
+        // the disk's radiance is the irradiance over its solid angle, which cancels with the
+        // uniform density of sampling it
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+        })
+    }
+}
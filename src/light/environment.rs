@@ -0,0 +1,251 @@
+use crate::film::color_luminance;
+use crate::math::{Color, Distribution2d, Mat4, Vec3};
+use crate::sampler::Sampler;
+use exr::prelude::read_first_rgba_layer_from_file;
+use image::codecs::hdr::HdrDecoder;
+use image::error::{DecodingError, ImageFormatHint};
+use image::{ImageError, ImageResult};
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Light arriving from infinitely far away, seen by rays that leave the scene.
+pub trait Environment: Send + Sync {
+    /// Radiance arriving from `direction`, a unit vector pointing away from the scene.
+    fn radiance(&self, direction: &Vec3) -> Color;
+
+    /// Samples a unit direction towards the environment, returning it with its solid angle
+    /// density, or `None` if the environment isn't worth sampling as a light.
+    #[allow(unused_variables)]
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        None
+    }
+
+    /// Solid angle density of [`Environment::sample`] choosing `direction`.
+    #[allow(unused_variables)]
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        0.0
+    }
+}
+
+/// The same color in every direction. Left to BSDF sampling, since every direction is as
+/// bright as any other.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantEnvironment {
+    color: Color,
+}
+
+impl ConstantEnvironment {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Environment for ConstantEnvironment {
+    fn radiance(&self, _direction: &Vec3) -> Color {
+        self.color
+    }
+}
+
+/// An environment lit by an equirectangular (latitude-longitude) HDR image.
+///
+/// The image's top row looks straight up (+y) and its bottom row straight down. Its left
+/// edge faces +x, a quarter of the way across faces +z and its middle faces -x, before
+/// [`EnvironmentMap::with_rotation`] turns it about the y axis. Directions are sampled in
+/// proportion to the luminance of the pixels they see, so a bright sun in the image is
+/// sampled far more often than the sky around it.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    distribution: LatLongDistribution,
+    intensity: f64,
+    to_world: Mat4,
+    to_map: Mat4,
+}
+
+impl EnvironmentMap {
+    /// Creates an environment map from `width` by `height` linear RGB pixels in row-major order.
+    ///
+    /// Panics if the image is empty or `pixels` doesn't match its size.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert!(
+            width > 0 && height > 0 && pixels.len() == width * height,
+            "EnvironmentMap pixels must match its size"
+        );
+
+        let func: Vec<f64> = pixels.iter().map(|pixel| color_luminance(*pixel)).collect();
+
+        Self {
+            width,
+            height,
+            distribution: LatLongDistribution::new(&func, width),
+            pixels,
+            intensity: 1.0,
+            to_world: Mat4::identity(),
+            to_map: Mat4::identity(),
+        }
+    }
+
+    /// Loads a Radiance (`.hdr`) or OpenEXR (`.exr`) image.
+    pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("hdr") => {
+                let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+                let metadata = decoder.metadata();
+                let pixels = decoder
+                    .read_image_hdr()?
+                    .into_iter()
+                    .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+                    .collect();
+                Ok(Self::new(
+                    metadata.width as usize,
+                    metadata.height as usize,
+                    pixels,
+                ))
+            }
+            Some("exr") => {
+                let image = read_first_rgba_layer_from_file(
+                    path,
+                    |resolution, _| {
+                        (
+                            resolution.width(),
+                            vec![Color::default(); resolution.width() * resolution.height()],
+                        )
+                    },
+                    |(width, pixels), position, (r, g, b, _a): (f32, f32, f32, f32)| {
+                        pixels[position.y() * *width + position.x()] =
+                            Color::new(r as f64, g as f64, b as f64)
+                    },
+                )
+                .map_err(|e| {
+                    ImageError::Decoding(DecodingError::new(
+                        ImageFormatHint::Name("OpenEXR".to_string()),
+                        e,
+                    ))
+                })?;
+
+                let (width, pixels) = image.layer_data.channel_data.pixels;
+                let height = pixels.len() / width;
+                Ok(Self::new(width, height, pixels))
+            }
+            _ => Err(ImageError::Unsupported(
+                ImageFormatHint::PathExtension(path.to_path_buf()).into(),
+            )),
+        }
+    }
+
+    /// Turns the map by `angle` degrees about the y axis.
+    pub fn with_rotation(mut self, angle: f64) -> Self {
+        self.to_world = Mat4::rotation_y(angle);
+        self.to_map = Mat4::rotation_y(-angle);
+        self
+    }
+
+    /// Scales the radiance of every pixel by `intensity`.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    fn pixel(&self, (u, v): (f64, f64)) -> Color {
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let direction = self.to_map.transform_vector(direction);
+        self.intensity * self.pixel(lat_long_uv(&direction))
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        let (direction, pdf) = self.distribution.sample(sampler.get_2d())?;
+        Some((self.to_world.transform_vector(&direction), pdf))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        self.distribution
+            .pdf(&self.to_map.transform_vector(direction))
+    }
+}
+
+/// Samples directions over the sphere in proportion to a table of luminance laid out like an
+/// [`EnvironmentMap`] image.
+pub(crate) struct LatLongDistribution {
+    distribution: Distribution2d,
+}
+
+impl LatLongDistribution {
+    /// Creates a distribution from `luminance`, a row-major table with `width` columns.
+    pub(crate) fn new(luminance: &[f64], width: usize) -> Self {
+        let height = luminance.len() / width;
+
+        // weight each entry by the solid angle it covers, which shrinks towards the poles
+        let func: Vec<f64> = luminance
+            .iter()
+            .enumerate()
+            .map(|(i, y)| {
+                let theta = PI * ((i / width) as f64 + 0.5) / height as f64;
+                y * theta.sin()
+            })
+            .collect();
+
+        Self {
+            distribution: Distribution2d::new(&func, width),
+        }
+    }
+
+    /// Samples a unit direction, returning it with its solid angle density.
+    pub(crate) fn sample(&self, u: (f64, f64)) -> Option<(Vec3, f64)> {
+        let ((u, v), uv_pdf) = self.distribution.sample(u);
+        let sin_theta = (PI * v).sin();
+        if uv_pdf <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+
+        // the uv square covers the sphere's 4 pi steradians unevenly, with
+        // dw = 2 pi^2 sin(theta) du dv
+        Some((
+            lat_long_direction(u, v),
+            uv_pdf / (2.0 * PI * PI * sin_theta),
+        ))
+    }
+
+    /// Solid angle density of sampling the unit vector `direction`.
+    pub(crate) fn pdf(&self, direction: &Vec3) -> f64 {
+        let (u, v) = lat_long_uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf((u, v)) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+/// Coordinates in `[0, 1)²` of a unit direction in an [`EnvironmentMap`] image.
+pub(crate) fn lat_long_uv(direction: &Vec3) -> (f64, f64) {
+    let phi = direction.z().atan2(direction.x());
+    let phi = if phi < 0.0 { phi + 2.0 * PI } else { phi };
+    let theta = direction.y().clamp(-1.0, 1.0).acos();
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Unit direction seen at coordinates `(u, v)` of an [`EnvironmentMap`] image.
+pub(crate) fn lat_long_direction(u: f64, v: f64) -> Vec3 {
+    let (phi, theta) = (2.0 * PI * u, PI * v);
+    Vec3::new(
+        theta.sin() * phi.cos(),
+        theta.cos(),
+        theta.sin() * phi.sin(),
+    )
+}
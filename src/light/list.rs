@@ -0,0 +1,134 @@
+use crate::film::luminance;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::material::Material;
+use crate::math::{Aabb, Point3, Ray, Vec3};
+use crate::sampler::Sampler;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// A list of emissive surfaces, each sampled in proportion to the power it emits.
+///
+/// [`LightList::from_hittables`] finds every surface in a scene whose material
+/// [emits light](Material::is_emissive), so lights never have to be listed separately from
+/// the geometry they belong to.
+pub struct LightList {
+    lights: Vec<Arc<dyn Hittable>>,
+    powers: Vec<f64>,
+    /// Running totals of `powers`, for choosing a light.
+    power_cdf: Vec<f64>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self {
+            lights: Vec::new(),
+            powers: Vec::new(),
+            power_cdf: Vec::new(),
+        }
+    }
+
+    /// Collects the emissive surfaces of every object in `hittables`.
+    pub fn from_hittables(hittables: &HittableList) -> Self {
+        let mut lights = Self::new();
+        for object in hittables.objects().iter() {
+            object.add_lights(object, None, &mut lights);
+        }
+        lights
+    }
+
+    /// Adds a light that emits `power` in total. Lights that emit nothing are left out, since
+    /// they would never be sampled.
+    pub fn add(&mut self, light: Arc<dyn Hittable>, power: f64) {
+        if power > 0.0 && power.is_finite() {
+            self.lights.push(light);
+            self.powers.push(power);
+            self.power_cdf.push(self.total_power() + power);
+        }
+    }
+
+    /// Adds a surface of the given `area` if `material` emits light. The surface must support
+    /// [`Hittable::pdf_value`] and [`Hittable::random`].
+    pub fn add_surface(&mut self, surface: Arc<dyn Hittable>, material: &dyn Material, area: f64) {
+        if material.is_emissive() {
+            let emitted = material.average_emitted();
+            let radiance =
+                luminance([emitted.x() as f32, emitted.y() as f32, emitted.z() as f32]) as f64;
+            // a diffuse emitter's radiant exitance is pi times its radiance
+            self.add(surface, PI * area * radiance);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub fn total_power(&self) -> f64 {
+        self.power_cdf.last().copied().unwrap_or(0.0)
+    }
+
+    /// Iterates over the lights and the power each emits.
+    pub fn iter(&self) -> impl Iterator<Item = (&Arc<dyn Hittable>, f64)> {
+        self.lights.iter().zip(self.powers.iter().copied())
+    }
+
+    /// Probability of the light at `index` being chosen for a light sample.
+    pub fn probability(&self, index: usize) -> f64 {
+        self.powers[index] / self.total_power()
+    }
+}
+
+impl Default for LightList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hittable for LightList {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest_so_far = t_max;
+        let mut hit: Option<HitRecord> = None;
+
+        for light in self.lights.iter() {
+            if let Some(temp_rec) = light.hit(ray, t_min, closest_so_far) {
+                closest_so_far = temp_rec.t;
+                hit = Some(temp_rec);
+            }
+        }
+
+        hit
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        let mut lights = self.lights.iter();
+        let mut output_box = lights.next()?.bounding_box(t0, t1)?;
+        for light in lights {
+            output_box = Aabb::surrounding_box(&output_box, &light.bounding_box(t0, t1)?);
+        }
+        Some(output_box)
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        self.lights
+            .iter()
+            .enumerate()
+            .map(|(index, light)| self.probability(index) * light.pdf_value(o, v))
+            .sum()
+    }
+
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        if self.lights.is_empty() {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+
+        let target = sampler.get_1d() * self.total_power();
+        let index = self
+            .power_cdf
+            .partition_point(|&power| power <= target)
+            .min(self.lights.len() - 1);
+        self.lights[index].random(o, sampler)
+    }
+}
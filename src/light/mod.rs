@@ -0,0 +1,41 @@
+//! Lights sampled for direct lighting.
+//!
+//! Emissive surfaces are collected into a [`LightList`] when a scene is created. Point, spot and
+//! directional lights have no surface for rays to hit, so they implement [`Light`] and are
+//! added to the scene separately. Light from beyond the scene, like an HDR environment map or
+//! a daylight [`Sky`], comes from the scene's [`Environment`].
+
+mod directional;
+mod environment;
+mod list;
+mod point;
+mod sky;
+mod spot;
+
+pub use directional::*;
+pub use environment::*;
+pub use list::*;
+pub use point::*;
+pub use sky::*;
+pub use spot::*;
+
+use crate::math::{Color, Point3, Vec3};
+use crate::sampler::Sampler;
+
+/// A light without geometry, which paths can only reach by sampling it.
+pub trait Light: Send + Sync {
+    /// Samples the light arriving at `point`, returning `None` if none arrives.
+    fn sample(&self, point: &Point3, sampler: &mut dyn Sampler) -> Option<LightSample>;
+}
+
+/// Light arriving at a point from a [`Light`].
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// Unit direction from the point towards the light.
+    pub direction: Vec3,
+    /// Distance to the light, or infinity for directional lights.
+    pub distance: f64,
+    /// Incident radiance divided by the probability density of sampling `direction`, which
+    /// for point and spot lights is just their intensity over the squared distance.
+    pub radiance: Color,
+}
@@ -0,0 +1,37 @@
+use super::{Light, LightSample};
+use crate::math::{Color, Point3};
+use crate::sampler::Sampler;
+
+/// A light emitting equally in every direction from a single point.
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+}
+
+impl PointLight {
+    /// Creates a point light with radiant `intensity` (power per unit solid angle).
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let dist_squared = to_light.length_squared();
+        if dist_squared == 0.0 {
+            return None;
+        }
+
+        let distance = dist_squared.sqrt();
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / dist_squared,
+        })
+    }
+}
@@ -0,0 +1,305 @@
+use super::environment::{lat_long_direction, LatLongDistribution};
+use super::Environment;
+use crate::film::color_luminance;
+use crate::math::{Color, Onb, Vec3};
+use crate::sampler::Sampler;
+use std::f64::consts::PI;
+
+/// Illuminance of sunlight above the atmosphere, in kilolux.
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+
+/// Wavelengths, in micrometres, standing in for the red, green and blue channels when light is
+/// attenuated through the atmosphere.
+const WAVELENGTHS: [f64; 3] = [0.680, 0.550, 0.440];
+
+/// Size of the table the sky is sampled from.
+const TABLE_WIDTH: usize = 128;
+const TABLE_HEIGHT: usize = 64;
+
+/// A clear daytime sky and sun, using the analytic model of Preetham, Shirley and Smits, "A
+/// Practical Analytic Model for Daylight" (1999).
+///
+/// The sky's brightness and color follow the sun's position and the turbidity of the air,
+/// from about 2 for a very clear day to 10 for thick haze. The sun is a disk of uniform
+/// radiance, reddened by the air it passes through as it nears the horizon. Below the horizon
+/// is an infinite diffuse ground lit by both. The model is only meant for a sun above the
+/// horizon.
+///
+/// Radiance is in thousands of candelas per square metre, scaled by the sky's intensity. The
+/// default intensity brings a white surface facing a midday sun to roughly one.
+///
+/// The sun and sky are importance sampled together, choosing between them by how much light
+/// each gives, so the sun casts clean shadows and the sky soft ones.
+pub struct Sky {
+    model: PreethamSky,
+    sun_frame: Onb,
+    sun_cos_radius: f64,
+    /// Irradiance on a surface facing the sun, after passing through the atmosphere.
+    sun_irradiance: Color,
+    ground: Color,
+    distribution: LatLongDistribution,
+    sun_probability: f64,
+    intensity: f64,
+}
+
+impl Sky {
+    pub const DEFAULT_TURBIDITY: f64 = 3.0;
+    /// Angular diameter of the sun seen from the earth, in degrees.
+    pub const DEFAULT_SUN_ANGULAR_DIAMETER: f64 = 0.53;
+    pub const DEFAULT_INTENSITY: f64 = 0.025;
+
+    /// Creates a sky lit by a sun in `sun_direction` (pointing towards the sun), above ground
+    /// with the given albedo.
+    ///
+    /// Panics if `sun_direction` is zero.
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Color) -> Self {
+        assert!(
+            !sun_direction.near_zero(),
+            "Sky sun direction must not be zero"
+        );
+        let model = PreethamSky::new(sun_direction.unit(), turbidity);
+        let sun_direction = model.sun_direction;
+        let sun_irradiance = sun_transmittance(model.theta_s, turbidity) * SOLAR_ILLUMINANCE;
+
+        // tabulate the sky to sample it from, and to find the light falling on the ground
+        let mut table = vec![0.0; TABLE_WIDTH * TABLE_HEIGHT];
+        let mut sky_irradiance = Color::default();
+        for (i, entry) in table.iter_mut().enumerate() {
+            let u = ((i % TABLE_WIDTH) as f64 + 0.5) / TABLE_WIDTH as f64;
+            let v = ((i / TABLE_WIDTH) as f64 + 0.5) / TABLE_HEIGHT as f64;
+            let direction = lat_long_direction(u, v);
+            if direction.y() <= 0.0 {
+                continue;
+            }
+
+            let radiance = model.radiance(&direction);
+            let solid_angle = 2.0 * PI * PI * (PI * v).sin() / (TABLE_WIDTH * TABLE_HEIGHT) as f64;
+            *entry = color_luminance(radiance);
+            sky_irradiance += radiance * direction.y() * solid_angle;
+        }
+
+        let ground =
+            ground_albedo * (sky_irradiance + sun_irradiance * sun_direction.y().max(0.0)) / PI;
+        let ground_luminance = color_luminance(ground);
+        for entry in table[TABLE_WIDTH * TABLE_HEIGHT / 2..].iter_mut() {
+            *entry = ground_luminance;
+        }
+
+        // choose between the sun and the rest of the sphere by the light each gives a surface
+        // facing it, which for the sun doesn't depend on the size of its disk
+        let sun_weight = if sun_direction.y() > 0.0 {
+            color_luminance(sun_irradiance)
+        } else {
+            0.0
+        };
+        let rest_weight = color_luminance(sky_irradiance) + PI * ground_luminance;
+
+        Self {
+            model,
+            sun_frame: Onb::new(&sun_direction),
+            sun_cos_radius: (Self::DEFAULT_SUN_ANGULAR_DIAMETER / 2.0)
+                .to_radians()
+                .cos(),
+            sun_irradiance,
+            ground,
+            distribution: LatLongDistribution::new(&table, TABLE_WIDTH),
+            sun_probability: sun_weight / (sun_weight + rest_weight),
+            intensity: Self::DEFAULT_INTENSITY,
+        }
+    }
+
+    /// Sets the apparent size of the sun disk in degrees, keeping the light it gives the same.
+    ///
+    /// Panics unless `angular_diameter` is positive.
+    pub fn with_sun_angular_diameter(mut self, angular_diameter: f64) -> Self {
+        assert!(
+            angular_diameter > 0.0,
+            "Sky sun angular diameter must be positive"
+        );
+        self.sun_cos_radius = (angular_diameter / 2.0).to_radians().cos();
+        self
+    }
+
+    /// Scales the radiance of the sky, sun and ground by `intensity`.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Solid angle of the sun disk.
+    fn sun_solid_angle(&self) -> f64 {
+        2.0 * PI * (1.0 - self.sun_cos_radius)
+    }
+
+    fn in_sun(&self, direction: &Vec3) -> bool {
+        let sun_direction = self.model.sun_direction;
+        sun_direction.y() > 0.0 && direction.dot(&sun_direction) >= self.sun_cos_radius
+    }
+}
+
+impl Environment for Sky {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        if direction.y() <= 0.0 {
+            return self.intensity * self.ground;
+        }
+
+        let mut radiance = self.model.radiance(direction);
+        if self.in_sun(direction) {
+            radiance += self.sun_irradiance / self.sun_solid_angle();
+        }
+        self.intensity * radiance
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        let choice = sampler.get_1d();
+        let u = sampler.get_2d();
+        let direction = if choice < self.sun_probability {
+            self.sun_frame
+                .local_vec(&Vec3::in_cone(self.sun_cos_radius, u))
+        } else {
+            self.distribution.sample(u)?.0
+        };
+
+        // either strategy could have chosen the direction
+        Some((direction, self.pdf(&direction)))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        let sun_pdf = if self.in_sun(direction) {
+            1.0 / self.sun_solid_angle()
+        } else {
+            0.0
+        };
+        self.sun_probability * sun_pdf
+            + (1.0 - self.sun_probability) * self.distribution.pdf(direction)
+    }
+}
+
+/// The sky of the Preetham model, without the sun.
+struct PreethamSky {
+    sun_direction: Vec3,
+    /// Zenith angle of the sun.
+    theta_s: f64,
+    /// Coefficients of the Perez distribution for luminance and the x and y chromaticities.
+    perez: [[f64; 5]; 3],
+    /// Luminance and chromaticities at the zenith.
+    zenith: [f64; 3],
+}
+
+impl PreethamSky {
+    fn new(sun_direction: Vec3, turbidity: f64) -> Self {
+        let t = turbidity;
+        let theta_s = sun_direction.y().clamp(0.0, 1.0).acos();
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        // [t^2, t, 1] * m * [theta_s^3, theta_s^2, theta_s, 1]
+        let zenith_chromaticity = |m: [[f64; 4]; 3]| {
+            let thetas = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+            [t * t, t, 1.0]
+                .iter()
+                .zip(m.iter())
+                .map(|(tk, row)| {
+                    tk * row
+                        .iter()
+                        .zip(thetas.iter())
+                        .map(|(a, b)| a * b)
+                        .sum::<f64>()
+                })
+                .sum::<f64>()
+        };
+        let zenith = [
+            zenith_luminance,
+            zenith_chromaticity([
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ]),
+            zenith_chromaticity([
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ]),
+        ];
+
+        Self {
+            sun_direction,
+            theta_s,
+            perez,
+            zenith,
+        }
+    }
+
+    /// Unscaled radiance along a unit direction above the horizon.
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let cos_theta = direction.y().max(1e-4);
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let mut values = [0.0; 3];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = self.zenith[i] * perez(&self.perez[i], cos_theta, gamma)
+                / perez(&self.perez[i], 1.0, self.theta_s);
+        }
+        let [luminance, x, y] = values;
+
+        // xyY to CIE XYZ, then to linear sRGB
+        let (cx, cy, cz) = (x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        Color::new(
+            (3.2406 * cx - 1.5372 * cy - 0.4986 * cz).max(0.0),
+            (-0.9689 * cx + 1.8758 * cy + 0.0415 * cz).max(0.0),
+            (0.0557 * cx - 0.2040 * cy + 1.0570 * cz).max(0.0),
+        )
+    }
+}
+
+/// The Perez sky luminance distribution, relative to an arbitrary scale, at zenith angle
+/// `acos(cos_theta)` and angle `gamma` from the sun.
+fn perez(coefficients: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+    (1.0 + a * (b / cos_theta).exp())
+        * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+}
+
+/// Fraction of sunlight at each of [`WAVELENGTHS`] that reaches the ground through the
+/// atmosphere, from Rayleigh scattering by air molecules and scattering by haze.
+fn sun_transmittance(theta_s: f64, turbidity: f64) -> Color {
+    // relative optical air mass, from Kasten and Young (1989)
+    let zenith_deg = theta_s.to_degrees().min(90.0);
+    let air_mass = 1.0 / (theta_s.cos() + 0.50572 * (96.07995 - zenith_deg).powf(-1.6364));
+    let beta = 0.04608 * turbidity - 0.04586;
+
+    let channel = |lambda: f64| {
+        let rayleigh = 0.008735 * lambda.powf(-4.08);
+        let aerosol = beta * lambda.powf(-1.3);
+        (-air_mass * (rayleigh + aerosol)).exp()
+    };
+    Color::new(
+        channel(WAVELENGTHS[0]),
+        channel(WAVELENGTHS[1]),
+        channel(WAVELENGTHS[2]),
+    )
+}
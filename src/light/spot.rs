@@ -0,0 +1,77 @@
+use super::{Light, LightSample};
+use crate::math::{Color, Point3, Vec3};
+use crate::sampler::Sampler;
+
+/// A point light that only shines within a cone.
+///
+/// The intensity is full out to `falloff_angle` from the cone's axis and eases smoothly to
+/// zero at `cone_angle`.
+#[derive(Debug, Clone, Copy)]
+pub struct SpotLight {
+    position: Point3,
+    axis: Vec3,
+    intensity: Color,
+    cos_cone: f64,
+    cos_falloff: f64,
+}
+
+impl SpotLight {
+    /// Creates a spot light at `position` shining towards `target`. Angles are in degrees from
+    /// the cone's axis, and `falloff_angle` is clamped to at most `cone_angle`.
+    ///
+    /// Panics if `target` is at `position`.
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        intensity: Color,
+        cone_angle: f64,
+        falloff_angle: f64,
+    ) -> Self {
+        assert!(
+            !(target - position).near_zero(),
+            "SpotLight target must differ from its position"
+        );
+        Self {
+            position,
+            axis: (target - position).unit(),
+            intensity,
+            cos_cone: cone_angle.to_radians().cos(),
+            cos_falloff: falloff_angle.min(cone_angle).to_radians().cos(),
+        }
+    }
+
+    /// Fraction of the full intensity emitted at `cos_theta` from the axis.
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff {
+            1.0
+        } else if cos_theta <= self.cos_cone {
+            0.0
+        } else {
+            let t = (cos_theta - self.cos_cone) / (self.cos_falloff - self.cos_cone);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_light = self.position - *point;
+        let dist_squared = to_light.length_squared();
+        if dist_squared == 0.0 {
+            return None;
+        }
+
+        let distance = dist_squared.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(-direction.dot(&self.axis));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * falloff / dist_squared,
+        })
+    }
+}
@@ -0,0 +1,63 @@
+//! Loaders for building scenes from files on disk
+
+mod obj;
+mod scene;
+
+pub use obj::*;
+pub use scene::*;
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Error produced while loading a scene asset.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(PathBuf, io::Error),
+    /// The file was read but its contents are invalid.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// The file is well-formed but describes something that can't be built.
+    Invalid(PathBuf, String),
+    /// A texture referenced by the file could not be opened.
+    Image(PathBuf, image::ImageError),
+}
+
+impl LoadError {
+    pub(crate) fn parse(path: &Path, line: usize, message: impl Into<String>) -> Self {
+        LoadError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            LoadError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            LoadError::Invalid(path, message) => write!(f, "{}: {}", path.display(), message),
+            LoadError::Image(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(_, err) => Some(err),
+            LoadError::Parse { .. } | LoadError::Invalid(..) => None,
+            LoadError::Image(_, err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use super::LoadError;
+use crate::bvh::{BvhNode, SahOptions};
+use crate::hittable::HittableList;
+use crate::material::*;
+use crate::math::{Color, Point3, Vec3};
+use crate::primitives::TriangleMesh;
+use crate::texture::{Image, Texture};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Loads a Wavefront `.obj` file and any `.mtl` libraries it references.
+///
+/// Faces are grouped by material into one [`TriangleMesh`] per material. Polygons with more than
+/// three vertices are triangulated as fans. If `use_bvh` is set, the meshes are wrapped in a
+/// single [`BvhNode`].
+///
+/// MTL parameters are mapped onto the closest available material:
+/// - non-black `Ke` becomes a [`DiffuseLight`]
+/// - `d < 1` or a refractive `illum` model (4, 6, 7, 9) becomes a [`Dielectric`] using `Ni`
+/// - a reflective `illum` model (3, 5, 8) becomes a [`Metal`] using `Ks`, with roughness
+///   derived from the `Ns` exponent
+/// - anything else becomes a [`Lambertian`] using `map_Kd` if present, otherwise `Kd`
+pub fn load_obj<P: AsRef<Path>>(path: P, use_bvh: bool) -> Result<HittableList, LoadError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut tex_coords: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut groups: Vec<MeshBuilder> = Vec::new();
+    let mut group_index: HashMap<Option<String>, usize> = HashMap::new();
+    let mut current_material: Option<String> = None;
+
+    for (line_num, line) in source.lines().enumerate() {
+        let line_num = line_num + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parse_vec3(path, line_num, &args)?),
+            "vn" => normals.push(parse_vec3(path, line_num, &args)?),
+            "vt" => {
+                let u = parse_f64(path, line_num, args.first())?;
+                let v = match args.get(1) {
+                    Some(_) => parse_f64(path, line_num, args.get(1))?,
+                    None => 0.0,
+                };
+                tex_coords.push((u, v));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(LoadError::parse(
+                        path,
+                        line_num,
+                        "face needs at least 3 vertices",
+                    ));
+                }
+
+                let mut face = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    face.push(parse_face_vertex(
+                        path,
+                        line_num,
+                        arg,
+                        positions.len(),
+                        tex_coords.len(),
+                        normals.len(),
+                    )?);
+                }
+
+                let index = *group_index
+                    .entry(current_material.clone())
+                    .or_insert_with(|| {
+                        groups.push(MeshBuilder::new(current_material.clone()));
+                        groups.len() - 1
+                    });
+                let group = &mut groups[index];
+
+                let face: Vec<usize> = face
+                    .into_iter()
+                    .map(|key| group.vertex(key, &positions, &tex_coords, &normals))
+                    .collect();
+                for i in 1..face.len() - 1 {
+                    group.indices.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            "mtllib" => {
+                if args.is_empty() {
+                    return Err(LoadError::parse(path, line_num, "mtllib needs a file name"));
+                }
+                materials.extend(load_mtl(&base_dir.join(args.join(" ")))?);
+            }
+            "usemtl" => {
+                if args.is_empty() {
+                    return Err(LoadError::parse(path, line_num, "usemtl needs a name"));
+                }
+                current_material = Some(args.join(" "));
+            }
+            // everything else, including object/group names ("o", "g") and smoothing groups
+            // ("s"), doesn't affect the geometry we build
+            _ => {}
+        }
+    }
+
+    let default_material: Arc<dyn Material> =
+        Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+
+    let mut list = HittableList::new();
+    for group in groups.into_iter() {
+        let material = match group.material {
+            Some(ref name) => materials
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| default_material.clone()),
+            None => default_material.clone(),
+        };
+        list.add(group.build(material));
+    }
+
+    if use_bvh && !list.objects().is_empty() {
+        Ok(BvhNode::from_list_sah(&list, 0.0, 1.0, SahOptions::default()).into())
+    } else {
+        Ok(list)
+    }
+}
+
+/// Position, texture coordinate and normal indices of a face vertex.
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+/// Accumulates the faces sharing one material, re-indexing OBJ's separate position, UV and
+/// normal indices into the single index buffer used by [`TriangleMesh`].
+struct MeshBuilder {
+    material: Option<String>,
+    positions: Vec<Point3>,
+    uvs: Vec<(f64, f64)>,
+    normals: Vec<Vec3>,
+    indices: Vec<[usize; 3]>,
+    vertex_map: HashMap<VertexKey, usize>,
+    missing_uvs: bool,
+    missing_normals: bool,
+}
+
+impl MeshBuilder {
+    fn new(material: Option<String>) -> Self {
+        Self {
+            material,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+            vertex_map: HashMap::new(),
+            missing_uvs: false,
+            missing_normals: false,
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        key: VertexKey,
+        positions: &[Point3],
+        tex_coords: &[(f64, f64)],
+        normals: &[Vec3],
+    ) -> usize {
+        if let Some(&index) = self.vertex_map.get(&key) {
+            return index;
+        }
+
+        let (p, vt, vn) = key;
+        self.positions.push(positions[p]);
+        match vt {
+            Some(vt) => self.uvs.push(tex_coords[vt]),
+            None => {
+                self.missing_uvs = true;
+                self.uvs.push((0.0, 0.0));
+            }
+        }
+        match vn {
+            Some(vn) => self.normals.push(normals[vn]),
+            None => {
+                self.missing_normals = true;
+                self.normals.push(Vec3::default());
+            }
+        }
+
+        let index = self.positions.len() - 1;
+        self.vertex_map.insert(key, index);
+        index
+    }
+
+    fn build(self, material: Arc<dyn Material>) -> TriangleMesh {
+        // TriangleMesh needs either all or none of the vertices to carry normals/UVs
+        let normals = if self.missing_normals {
+            Vec::new()
+        } else {
+            self.normals
+        };
+        let uvs = if self.missing_uvs {
+            Vec::new()
+        } else {
+            self.uvs
+        };
+
+        TriangleMesh::new(self.positions, normals, uvs, self.indices, Some(material))
+    }
+}
+
+/// Material parameters read from a `newmtl` block.
+struct MtlMaterial {
+    kd: Color,
+    ks: Color,
+    ke: Color,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    illum: u32,
+    map_kd: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::default(),
+            ke: Color::default(),
+            ns: 0.0,
+            ni: 1.5,
+            d: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    fn to_material(&self) -> Result<Arc<dyn Material>, LoadError> {
+        if !self.ke.near_zero() {
+            return Ok(Arc::new(DiffuseLight::new(self.ke)));
+        }
+
+        if self.d < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            return Ok(Arc::new(Dielectric::new(self.ni)));
+        }
+
+        if matches!(self.illum, 3 | 5 | 8) {
+            // a Phong lobe with exponent Ns is about as wide as a GGX lobe with alpha
+            // sqrt(2 / (Ns + 2)), and roughness is the square root of alpha
+            let roughness = (2.0 / (self.ns + 2.0)).sqrt().sqrt();
+            return Ok(Arc::new(Metal::new(self.ks, roughness)));
+        }
+
+        if let Some(ref map) = self.map_kd {
+            let texture: Arc<dyn Texture> =
+                Arc::new(Image::open(map).map_err(|e| LoadError::Image(map.clone(), e))?);
+            return Ok(Arc::new(Lambertian::from(texture)));
+        }
+
+        Ok(Arc::new(Lambertian::new(self.kd)))
+    }
+}
+
+/// Loads every material in a `.mtl` library.
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, LoadError> {
+    let source = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut parsed: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (line_num, line) in source.lines().enumerate() {
+        let line_num = line_num + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                return Err(LoadError::parse(path, line_num, "newmtl needs a name"));
+            }
+            parsed.push((args.join(" "), MtlMaterial::default()));
+            continue;
+        }
+
+        let material = match parsed.last_mut() {
+            Some((_, material)) => material,
+            None => continue,
+        };
+
+        match keyword {
+            "Kd" => material.kd = parse_vec3(path, line_num, &args)?,
+            "Ks" => material.ks = parse_vec3(path, line_num, &args)?,
+            "Ke" => material.ke = parse_vec3(path, line_num, &args)?,
+            "Ns" => material.ns = parse_f64(path, line_num, args.first())?,
+            "Ni" => material.ni = parse_f64(path, line_num, args.first())?,
+            "d" => material.d = parse_f64(path, line_num, args.first())?,
+            "Tr" => material.d = 1.0 - parse_f64(path, line_num, args.first())?,
+            "illum" => {
+                material.illum = args
+                    .first()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| LoadError::parse(path, line_num, "expected illum model"))?
+            }
+            "map_Kd" => {
+                // texture options (-s, -o, ...) precede the file name, which is always last
+                let file = args
+                    .last()
+                    .ok_or_else(|| LoadError::parse(path, line_num, "map_Kd needs a file name"))?;
+                material.map_kd = Some(base_dir.join(file));
+            }
+            _ => {}
+        }
+    }
+
+    let mut materials = HashMap::new();
+    for (name, material) in parsed.into_iter() {
+        materials.insert(name, material.to_material()?);
+    }
+
+    Ok(materials)
+}
+
+fn parse_f64(path: &Path, line: usize, token: Option<&&str>) -> Result<f64, LoadError> {
+    token
+        .and_then(|s| s.parse::<f64>().ok())
+        .ok_or_else(|| LoadError::parse(path, line, "expected a number"))
+}
+
+fn parse_vec3(path: &Path, line: usize, args: &[&str]) -> Result<Vec3, LoadError> {
+    Ok(Vec3::new(
+        parse_f64(path, line, args.first())?,
+        parse_f64(path, line, args.get(1))?,
+        parse_f64(path, line, args.get(2))?,
+    ))
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face vertex into zero-based indices.
+fn parse_face_vertex(
+    path: &Path,
+    line: usize,
+    token: &str,
+    num_positions: usize,
+    num_tex_coords: usize,
+    num_normals: usize,
+) -> Result<VertexKey, LoadError> {
+    let mut parts = token.split('/');
+    let position = match parts.next() {
+        Some(s) => resolve_index(path, line, s, num_positions)?,
+        None => return Err(LoadError::parse(path, line, "missing vertex index")),
+    };
+    let tex_coord = match parts.next() {
+        Some(s) if !s.is_empty() => Some(resolve_index(path, line, s, num_tex_coords)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(s) if !s.is_empty() => Some(resolve_index(path, line, s, num_normals)?),
+        _ => None,
+    };
+
+    Ok((position, tex_coord, normal))
+}
+
+/// Converts a one-based (or negative, relative) OBJ index into a zero-based index.
+fn resolve_index(path: &Path, line: usize, token: &str, len: usize) -> Result<usize, LoadError> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| LoadError::parse(path, line, format!("invalid index '{}'", token)))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as i64 + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(LoadError::parse(
+            path,
+            line,
+            format!("index {} out of range", index),
+        ));
+    }
+
+    Ok(resolved as usize)
+}
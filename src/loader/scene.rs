@@ -0,0 +1,838 @@
+use super::{load_obj, LoadError};
+use crate::bvh::{BvhNode, SahOptions};
+use crate::camera::Camera;
+use crate::hittable::{FlipFace, Hittable, HittableList, Instance, Transform};
+use crate::light::{DirectionalLight, EnvironmentMap, PointLight, Sky, SpotLight};
+use crate::material::*;
+use crate::math::{Mat4, Vec3};
+use crate::primitives::*;
+use crate::scene::Scene;
+use crate::texture::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Loads a TOML scene file and builds a [`Scene`] for an image with the given aspect ratio.
+pub fn load_scene<P: AsRef<Path>>(path: P, aspect_ratio: f64) -> Result<Scene, LoadError> {
+    SceneFile::open(path)?.build(aspect_ratio)
+}
+
+/// A parsed TOML scene description.
+///
+/// A scene file has a `[camera]` table, an optional `background` color or `[environment]`,
+/// named `[textures.*]`, `[materials.*]` and `[geometry.*]` tables, an `[[objects]]` array of
+/// primitives and a `[[lights]]` array. Objects refer to materials by name, and file paths are
+/// resolved relative to the scene file. The format is described in the README.
+#[derive(Deserialize)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub background: [f64; 3],
+    environment: Option<EnvironmentDesc>,
+    pub camera: CameraDesc,
+    /// Wrap the scene objects in a [`BvhNode`].
+    #[serde(default)]
+    pub bvh: bool,
+    #[serde(default)]
+    textures: HashMap<String, TextureDesc>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDesc>,
+    #[serde(default)]
+    geometry: HashMap<String, ObjectDesc>,
+    #[serde(default)]
+    objects: Vec<ObjectDesc>,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Camera settings of a [`SceneFile`]. The aspect ratio comes from the output image instead.
+#[derive(Deserialize)]
+pub struct CameraDesc {
+    pub look_from: [f64; 3],
+    pub look_at: [f64; 3],
+    #[serde(default = "default_up")]
+    pub up: [f64; 3],
+    #[serde(default = "default_fov")]
+    pub fov: f64,
+    #[serde(default)]
+    pub aperture: f64,
+    #[serde(default = "default_focus_dist")]
+    pub focus_dist: f64,
+    #[serde(default)]
+    pub time_0: f64,
+    #[serde(default = "default_time_1")]
+    pub time_1: f64,
+}
+
+fn default_up() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_fov() -> f64 {
+    20.0
+}
+
+fn default_focus_dist() -> f64 {
+    10.0
+}
+
+fn default_time_1() -> f64 {
+    1.0
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn default_absorption_distance() -> f64 {
+    1.0
+}
+
+fn default_principled_ior() -> f64 {
+    Principled::DEFAULT_IOR
+}
+
+fn default_turbidity() -> f64 {
+    Sky::DEFAULT_TURBIDITY
+}
+
+fn default_ground_albedo() -> [f64; 3] {
+    [0.3, 0.3, 0.3]
+}
+
+fn default_sun_angular_diameter() -> f64 {
+    Sky::DEFAULT_SUN_ANGULAR_DIAMETER
+}
+
+fn default_sky_intensity() -> f64 {
+    Sky::DEFAULT_INTENSITY
+}
+
+/// Either a literal RGB color or the name of a texture.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorOrTexture {
+    Color([f64; 3]),
+    Texture(String),
+}
+
+/// Either a literal number or the name of a texture, whose first channel is read.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ValueOrTexture {
+    Value(f64),
+    Texture(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TextureDesc {
+    Solid {
+        color: [f64; 3],
+    },
+    Checker {
+        even: ColorOrTexture,
+        odd: ColorOrTexture,
+    },
+    Perlin {
+        scale: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+    Image {
+        file: PathBuf,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum MaterialDesc {
+    Lambertian {
+        albedo: ColorOrTexture,
+    },
+    Metal {
+        albedo: Option<[f64; 3]>,
+        preset: Option<String>,
+        eta: Option<[f64; 3]>,
+        k: Option<[f64; 3]>,
+        #[serde(default, alias = "fuzz")]
+        roughness: f64,
+        /// Defaults to `roughness`, for highlights that aren't stretched.
+        roughness_v: Option<f64>,
+    },
+    Dielectric {
+        ior: f64,
+        #[serde(default)]
+        roughness: f64,
+        absorption_color: Option<[f64; 3]>,
+        #[serde(default = "default_absorption_distance")]
+        absorption_distance: f64,
+    },
+    Principled {
+        base_color: ColorOrTexture,
+        metallic: Option<ValueOrTexture>,
+        roughness: Option<ValueOrTexture>,
+        specular: Option<ValueOrTexture>,
+        specular_tint: Option<ValueOrTexture>,
+        sheen: Option<ValueOrTexture>,
+        clearcoat: Option<ValueOrTexture>,
+        transmission: Option<ValueOrTexture>,
+        anisotropy: Option<ValueOrTexture>,
+        #[serde(default = "default_principled_ior")]
+        ior: f64,
+    },
+    DiffuseLight {
+        emit: ColorOrTexture,
+    },
+    Isotropic {
+        albedo: ColorOrTexture,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LightDesc {
+    Point {
+        position: [f64; 3],
+        intensity: [f64; 3],
+    },
+    Spot {
+        position: [f64; 3],
+        target: [f64; 3],
+        intensity: [f64; 3],
+        cone_angle: f64,
+        /// Defaults to `cone_angle`, for a hard-edged spot.
+        falloff_angle: Option<f64>,
+    },
+    Directional {
+        direction: [f64; 3],
+        irradiance: [f64; 3],
+        #[serde(default)]
+        angular_diameter: f64,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EnvironmentDesc {
+    Map {
+        file: PathBuf,
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+    Sky {
+        sun_direction: [f64; 3],
+        #[serde(default = "default_turbidity")]
+        turbidity: f64,
+        #[serde(default = "default_ground_albedo")]
+        ground_albedo: [f64; 3],
+        #[serde(default = "default_sun_angular_diameter")]
+        sun_angular_diameter: f64,
+        #[serde(default = "default_sky_intensity")]
+        intensity: f64,
+    },
+}
+
+#[derive(Deserialize)]
+struct ObjectDesc {
+    #[serde(flatten)]
+    shape: ShapeDesc,
+    material: Option<String>,
+    /// Transforms applied in order, so the first entry is applied to the object first.
+    #[serde(default)]
+    transform: Vec<TransformDesc>,
+    #[serde(default)]
+    flip_face: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ShapeDesc {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+    },
+    MovingSphere {
+        center_0: [f64; 3],
+        center_1: [f64; 3],
+        radius: f64,
+        time_0: f64,
+        time_1: f64,
+    },
+    XyRect {
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+    },
+    XzRect {
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+    },
+    YzRect {
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+    },
+    Box {
+        min: [f64; 3],
+        max: [f64; 3],
+    },
+    Triangle {
+        vertices: [[f64; 3]; 3],
+    },
+    Mesh {
+        positions: Vec<[f64; 3]>,
+        indices: Vec<[usize; 3]>,
+        #[serde(default)]
+        normals: Vec<[f64; 3]>,
+        #[serde(default)]
+        uvs: Vec<[f64; 2]>,
+    },
+    /// A Wavefront OBJ model. Its materials come from the model's MTL libraries.
+    Obj {
+        file: PathBuf,
+        #[serde(default)]
+        bvh: bool,
+    },
+    ConstantMedium {
+        boundary: Box<ObjectDesc>,
+        density: f64,
+        color: ColorOrTexture,
+    },
+    /// A placement of shared geometry from the `[geometry]` table.
+    Instance {
+        geometry: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TransformDesc {
+    Translate([f64; 3]),
+    Scale(ScaleDesc),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Rotate { axis: [f64; 3], angle: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDesc {
+    Uniform(f64),
+    Axes([f64; 3]),
+}
+
+impl SceneFile {
+    /// Reads and parses a scene file without building any of its objects.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+        let mut scene: SceneFile = toml::from_str(&source)
+            .map_err(|e| LoadError::Invalid(path.to_path_buf(), e.to_string()))?;
+        scene.path = path.to_path_buf();
+        Ok(scene)
+    }
+
+    /// Builds the described [`Scene`] for an image with the given aspect ratio.
+    pub fn build(&self, aspect_ratio: f64) -> Result<Scene, LoadError> {
+        let mut builder = SceneBuilder {
+            file: self,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            geometry: HashMap::new(),
+            visiting: HashSet::new(),
+        };
+
+        let mut hittables = HittableList::new();
+
+        for object in self.objects.iter() {
+            hittables.add_shared(builder.object(object, false)?);
+        }
+
+        let hittables = if self.bvh && !hittables.objects().is_empty() {
+            BvhNode::from_list_sah(
+                &hittables,
+                self.camera.time_0,
+                self.camera.time_1,
+                SahOptions::default(),
+            )
+            .into()
+        } else {
+            hittables
+        };
+
+        let camera = Camera::new(
+            to_vec3(self.camera.look_from),
+            to_vec3(self.camera.look_at),
+            to_vec3(self.camera.up),
+            self.camera.fov,
+            aspect_ratio,
+            self.camera.aperture,
+            self.camera.focus_dist,
+            self.camera.time_0,
+            self.camera.time_1,
+        );
+
+        let mut scene = Scene::new(hittables, to_vec3(self.background), camera);
+        match self.environment {
+            Some(EnvironmentDesc::Map {
+                ref file,
+                rotation,
+                intensity,
+            }) => {
+                let file = self.resolve(file);
+                let map = EnvironmentMap::open(&file).map_err(|e| LoadError::Image(file, e))?;
+                scene.set_environment(Box::new(
+                    map.with_rotation(rotation).with_intensity(intensity),
+                ));
+            }
+            Some(EnvironmentDesc::Sky {
+                sun_direction,
+                turbidity,
+                ground_albedo,
+                sun_angular_diameter,
+                intensity,
+            }) => {
+                let sun_direction = to_vec3(sun_direction);
+                if sun_direction.near_zero() {
+                    return Err(builder.invalid("sky sun_direction must not be zero"));
+                }
+                if sun_angular_diameter <= 0.0 || sun_angular_diameter.is_nan() {
+                    return Err(builder.invalid("sky sun_angular_diameter must be positive"));
+                }
+                scene.set_environment(Box::new(
+                    Sky::new(sun_direction, turbidity, to_vec3(ground_albedo))
+                        .with_sun_angular_diameter(sun_angular_diameter)
+                        .with_intensity(intensity),
+                ));
+            }
+            None => {}
+        }
+        for light in self.lights.iter() {
+            match *light {
+                LightDesc::Point {
+                    position,
+                    intensity,
+                } => scene.add_light(PointLight::new(to_vec3(position), to_vec3(intensity))),
+                LightDesc::Spot {
+                    position,
+                    target,
+                    intensity,
+                    cone_angle,
+                    falloff_angle,
+                } => {
+                    if (to_vec3(target) - to_vec3(position)).near_zero() {
+                        return Err(
+                            builder.invalid("spot light target must differ from its position")
+                        );
+                    }
+                    scene.add_light(SpotLight::new(
+                        to_vec3(position),
+                        to_vec3(target),
+                        to_vec3(intensity),
+                        cone_angle,
+                        falloff_angle.unwrap_or(cone_angle),
+                    ))
+                }
+                LightDesc::Directional {
+                    direction,
+                    irradiance,
+                    angular_diameter,
+                } => {
+                    if to_vec3(direction).near_zero() {
+                        return Err(builder.invalid("directional light direction must not be zero"));
+                    }
+                    scene.add_light(DirectionalLight::new(
+                        to_vec3(direction),
+                        to_vec3(irradiance),
+                        angular_diameter,
+                    ))
+                }
+            }
+        }
+
+        Ok(scene)
+    }
+
+    fn resolve(&self, file: &Path) -> PathBuf {
+        match self.path.parent() {
+            Some(dir) => dir.join(file),
+            None => file.to_path_buf(),
+        }
+    }
+}
+
+/// Resolves named textures and materials while the scene's objects are built.
+struct SceneBuilder<'a> {
+    file: &'a SceneFile,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    geometry: HashMap<String, Arc<dyn Hittable>>,
+    visiting: HashSet<String>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn invalid(&self, message: impl Into<String>) -> LoadError {
+        LoadError::Invalid(self.file.path.clone(), message.into())
+    }
+
+    fn texture(&mut self, name: &str) -> Result<Arc<dyn Texture>, LoadError> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(texture.clone());
+        }
+
+        let file = self.file;
+        let desc = file
+            .textures
+            .get(name)
+            .ok_or_else(|| self.invalid(format!("unknown texture '{}'", name)))?;
+
+        // checker textures can refer to other textures, so guard against cycles
+        if !self.visiting.insert(name.to_string()) {
+            return Err(self.invalid(format!("texture '{}' refers to itself", name)));
+        }
+
+        let texture: Arc<dyn Texture> = match desc {
+            TextureDesc::Solid { color } => Arc::new(SolidColor::new(to_vec3(*color))),
+            TextureDesc::Checker { even, odd } => Arc::new(CheckerPattern::from_textures(
+                self.color_or_texture(even)?,
+                self.color_or_texture(odd)?,
+            )),
+            TextureDesc::Perlin { scale, seed } => Arc::new(PerlinNoise::with_seed(*scale, *seed)),
+            TextureDesc::Image { file: image } => {
+                let image = file.resolve(image);
+                Arc::new(Image::open(&image).map_err(|e| LoadError::Image(image, e))?)
+            }
+        };
+
+        self.visiting.remove(name);
+        self.textures.insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    fn color_or_texture(&mut self, value: &ColorOrTexture) -> Result<Arc<dyn Texture>, LoadError> {
+        match value {
+            ColorOrTexture::Color(color) => Ok(Arc::new(SolidColor::new(to_vec3(*color)))),
+            ColorOrTexture::Texture(name) => self.texture(name),
+        }
+    }
+
+    fn value_or_texture(&mut self, value: &ValueOrTexture) -> Result<Arc<dyn Texture>, LoadError> {
+        match value {
+            ValueOrTexture::Value(value) => {
+                Ok(Arc::new(SolidColor::new(Vec3::new(*value, *value, *value))))
+            }
+            ValueOrTexture::Texture(name) => self.texture(name),
+        }
+    }
+
+    fn material(&mut self, name: &str) -> Result<Arc<dyn Material>, LoadError> {
+        if let Some(material) = self.materials.get(name) {
+            return Ok(material.clone());
+        }
+
+        let file = self.file;
+        let desc = file
+            .materials
+            .get(name)
+            .ok_or_else(|| self.invalid(format!("unknown material '{}'", name)))?;
+
+        let material: Arc<dyn Material> = match desc {
+            MaterialDesc::Lambertian { albedo } => {
+                Arc::new(Lambertian::from(self.color_or_texture(albedo)?))
+            }
+            MaterialDesc::Metal {
+                albedo,
+                preset,
+                eta,
+                k,
+                roughness,
+                roughness_v,
+            } => {
+                let metal = match (albedo, preset, eta, k) {
+                    (Some(albedo), None, None, None) => Metal::new(to_vec3(*albedo), *roughness),
+                    (None, Some(preset), None, None) => metal_by_name(preset, *roughness)
+                        .ok_or_else(|| {
+                            self.invalid(format!(
+                                "unknown metal preset '{}', expected one of: {}",
+                                preset,
+                                METAL_NAMES.join(", ")
+                            ))
+                        })?,
+                    (None, None, Some(eta), Some(k)) => {
+                        Metal::conductor(to_vec3(*eta), to_vec3(*k), *roughness)
+                    }
+                    _ => {
+                        return Err(self.invalid(format!(
+                            "metal '{}' needs exactly one of albedo, preset, or eta and k",
+                            name
+                        )))
+                    }
+                };
+                Arc::new(metal.with_roughness(*roughness, roughness_v.unwrap_or(*roughness)))
+            }
+            MaterialDesc::Dielectric {
+                ior,
+                roughness,
+                absorption_color,
+                absorption_distance,
+            } => {
+                let mut dielectric = Dielectric::new(*ior).with_roughness(*roughness);
+                if let Some(color) = absorption_color {
+                    if *absorption_distance <= 0.0 || absorption_distance.is_nan() {
+                        return Err(self.invalid(format!(
+                            "dielectric '{}' absorption_distance must be positive",
+                            name
+                        )));
+                    }
+                    dielectric = dielectric.with_absorption(to_vec3(*color), *absorption_distance);
+                }
+                Arc::new(dielectric)
+            }
+            MaterialDesc::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                specular_tint,
+                sheen,
+                clearcoat,
+                transmission,
+                anisotropy,
+                ior,
+            } => {
+                let mut principled =
+                    Principled::new(self.color_or_texture(base_color)?).with_ior(*ior);
+                if let Some(metallic) = metallic {
+                    principled = principled.with_metallic(self.value_or_texture(metallic)?);
+                }
+                if let Some(roughness) = roughness {
+                    principled = principled.with_roughness(self.value_or_texture(roughness)?);
+                }
+                if let Some(specular) = specular {
+                    principled = principled.with_specular(self.value_or_texture(specular)?);
+                }
+                if let Some(specular_tint) = specular_tint {
+                    principled =
+                        principled.with_specular_tint(self.value_or_texture(specular_tint)?);
+                }
+                if let Some(sheen) = sheen {
+                    principled = principled.with_sheen(self.value_or_texture(sheen)?);
+                }
+                if let Some(clearcoat) = clearcoat {
+                    principled = principled.with_clearcoat(self.value_or_texture(clearcoat)?);
+                }
+                if let Some(transmission) = transmission {
+                    principled = principled.with_transmission(self.value_or_texture(transmission)?);
+                }
+                if let Some(anisotropy) = anisotropy {
+                    principled = principled.with_anisotropy(self.value_or_texture(anisotropy)?);
+                }
+                Arc::new(principled)
+            }
+            MaterialDesc::DiffuseLight { emit } => {
+                Arc::new(DiffuseLight::from(self.color_or_texture(emit)?))
+            }
+            MaterialDesc::Isotropic { albedo } => {
+                Arc::new(Isotropic::from(self.color_or_texture(albedo)?))
+            }
+        };
+
+        self.materials.insert(name.to_string(), material.clone());
+        Ok(material)
+    }
+
+    /// Builds shared geometry the first time it is instanced.
+    fn geometry(&mut self, name: &str) -> Result<Arc<dyn Hittable>, LoadError> {
+        if let Some(geometry) = self.geometry.get(name) {
+            return Ok(geometry.clone());
+        }
+
+        let file = self.file;
+        let desc = file
+            .geometry
+            .get(name)
+            .ok_or_else(|| self.invalid(format!("unknown geometry '{}'", name)))?;
+
+        // geometry can itself be made of instances, so guard against cycles
+        let key = format!("geometry.{}", name);
+        if !self.visiting.insert(key.clone()) {
+            return Err(self.invalid(format!("geometry '{}' instances itself", name)));
+        }
+
+        let geometry = self.object(desc, false)?;
+
+        self.visiting.remove(&key);
+        self.geometry.insert(name.to_string(), geometry.clone());
+        Ok(geometry)
+    }
+
+    /// Builds an object. Volume boundaries are built `bare`, skipping their material and face
+    /// flip.
+    fn object(&mut self, desc: &ObjectDesc, bare: bool) -> Result<Arc<dyn Hittable>, LoadError> {
+        let material = match desc.material {
+            Some(ref name) if !bare => Some(self.material(name)?),
+            _ => None,
+        };
+
+        let matrix = if desc.transform.is_empty() {
+            None
+        } else {
+            for transform in desc.transform.iter() {
+                if let TransformDesc::Rotate { axis, .. } = *transform {
+                    if to_vec3(axis).near_zero() {
+                        return Err(self.invalid("rotate axis must not be zero"));
+                    }
+                }
+            }
+            let matrix = desc
+                .transform
+                .iter()
+                .fold(Mat4::identity(), |matrix, transform| {
+                    transform.matrix() * matrix
+                });
+            if matrix.inverse().is_none() {
+                return Err(self.invalid("object transform is not invertible"));
+            }
+            Some(matrix)
+        };
+
+        let mut object: Arc<dyn Hittable> = match desc.shape {
+            ShapeDesc::Sphere { center, radius } => {
+                Arc::new(Sphere::new(to_vec3(center), radius, material))
+            }
+            ShapeDesc::MovingSphere {
+                center_0,
+                center_1,
+                radius,
+                time_0,
+                time_1,
+            } => Arc::new(MovingSphere::new(
+                to_vec3(center_0),
+                to_vec3(center_1),
+                radius,
+                time_0,
+                time_1,
+                material,
+            )),
+            ShapeDesc::XyRect { x0, x1, y0, y1, k } => {
+                Arc::new(XyPlane::new(x0, x1, y0, y1, k, material))
+            }
+            ShapeDesc::XzRect { x0, x1, z0, z1, k } => {
+                Arc::new(XzPlane::new(x0, x1, z0, z1, k, material))
+            }
+            ShapeDesc::YzRect { y0, y1, z0, z1, k } => {
+                Arc::new(YzPlane::new(y0, y1, z0, z1, k, material))
+            }
+            ShapeDesc::Box { min, max } => {
+                Arc::new(AaBox::new(to_vec3(min), to_vec3(max), material))
+            }
+            ShapeDesc::Triangle { vertices } => Arc::new(Triangle::new(
+                to_vec3(vertices[0]),
+                to_vec3(vertices[1]),
+                to_vec3(vertices[2]),
+                material,
+            )),
+            ShapeDesc::Mesh {
+                ref positions,
+                ref indices,
+                ref normals,
+                ref uvs,
+            } => {
+                if indices.is_empty() {
+                    return Err(self.invalid("mesh needs at least one face"));
+                }
+                if indices.iter().flatten().any(|&i| i >= positions.len()) {
+                    return Err(self.invalid("mesh index out of range"));
+                }
+                if (!normals.is_empty() && normals.len() != positions.len())
+                    || (!uvs.is_empty() && uvs.len() != positions.len())
+                {
+                    return Err(self.invalid("mesh normals and uvs must match its positions"));
+                }
+
+                Arc::new(TriangleMesh::new(
+                    positions.iter().map(|p| to_vec3(*p)).collect(),
+                    normals.iter().map(|n| to_vec3(*n)).collect(),
+                    uvs.iter().map(|uv| (uv[0], uv[1])).collect(),
+                    indices.clone(),
+                    material,
+                ))
+            }
+            ShapeDesc::Obj { ref file, bvh } => {
+                let list = load_obj(self.file.resolve(file), bvh)?;
+                if list.objects().is_empty() {
+                    return Err(self.invalid(format!("'{}' has no faces", file.display())));
+                }
+                Arc::new(list)
+            }
+            ShapeDesc::ConstantMedium {
+                ref boundary,
+                density,
+                ref color,
+            } => {
+                if density <= 0.0 || density.is_nan() {
+                    return Err(self.invalid("constant_medium density must be positive"));
+                }
+                let boundary = self.object(boundary, true)?;
+                Arc::new(ConstantMedium::from_texture(
+                    boundary,
+                    density,
+                    self.color_or_texture(color)?,
+                ))
+            }
+            ShapeDesc::Instance { ref geometry } => {
+                let geometry = self.geometry(geometry)?;
+                let instance = Instance::new(geometry, matrix.unwrap_or_default());
+                let instance = match material {
+                    Some(material) => instance.with_material(material),
+                    None => instance,
+                };
+                Arc::new(instance)
+            }
+        };
+
+        if let Some(matrix) = matrix {
+            if !matches!(desc.shape, ShapeDesc::Instance { .. }) {
+                object = Arc::new(Transform::new(object, matrix));
+            }
+        }
+
+        if desc.flip_face && !bare {
+            object = Arc::new(FlipFace::new(object));
+        }
+
+        Ok(object)
+    }
+}
+
+impl TransformDesc {
+    fn matrix(&self) -> Mat4 {
+        match *self {
+            TransformDesc::Translate(offset) => Mat4::translation(to_vec3(offset)),
+            TransformDesc::Scale(ScaleDesc::Uniform(s)) => Mat4::scaling(Vec3::new(s, s, s)),
+            TransformDesc::Scale(ScaleDesc::Axes(s)) => Mat4::scaling(to_vec3(s)),
+            TransformDesc::RotateX(angle) => Mat4::rotation_x(angle),
+            TransformDesc::RotateY(angle) => Mat4::rotation_y(angle),
+            TransformDesc::RotateZ(angle) => Mat4::rotation_z(angle),
+            TransformDesc::Rotate { axis, angle } => Mat4::rotation(to_vec3(axis), angle),
+        }
+    }
+}
+
+fn to_vec3(v: [f64; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
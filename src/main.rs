@@ -0,0 +1,269 @@
+use clap::{App, Arg, ArgMatches};
+use raytracer::film::{filter_by_name, tone_map_by_name, FILTER_NAMES, TONE_MAP_NAMES};
+use raytracer::integrator::{integrator_by_name, INTEGRATOR_NAMES};
+use raytracer::loader::SceneFile;
+use raytracer::sampler::{sampler_by_name, SAMPLER_NAMES};
+use raytracer::scene::AdaptiveOptions;
+use std::process;
+
+fn main() {
+    let matches = match_args();
+
+    // Parse image args
+    let image_height: usize = matches.value_of("height").unwrap().parse().unwrap();
+    let image_width: usize = matches.value_of("width").unwrap().parse().unwrap();
+    let aspect_ratio = image_width as f64 / image_height as f64;
+    let samples_per_pixel: u64 = matches.value_of("samples").unwrap().parse().unwrap();
+    let max_depth: u64 = matches.value_of("bounces").unwrap().parse().unwrap();
+
+    // Parse filenames
+    let scene_file = matches.value_of("scene").unwrap();
+    let filename = matches.value_of("output").unwrap();
+
+    // Parse output args
+    let white = matches.value_of("white").map(|w| w.parse().unwrap());
+    let tone_map = tone_map_by_name(matches.value_of("tonemap").unwrap(), white).unwrap();
+    let integrator = integrator_by_name(matches.value_of("integrator").unwrap()).unwrap();
+    let seed: u64 = matches.value_of("seed").unwrap().parse().unwrap();
+    let sampler = sampler_by_name(matches.value_of("sampler").unwrap(), seed).unwrap();
+    let filter_radius = matches.value_of("filterradius").map(|r| r.parse().unwrap());
+    let filter = filter_by_name(matches.value_of("filter").unwrap(), filter_radius)
+        .unwrap_or_else(|| exit_with_error("filter radius must be positive"));
+    let exposure: f32 = matches.value_of("exposure").unwrap().parse().unwrap();
+
+    let mut scene_desc = SceneFile::open(scene_file).unwrap_or_else(|e| exit_with_error(e));
+
+    // Camera options on the command line override the scene file
+    if let Some(fov) = matches.value_of("fov") {
+        scene_desc.camera.fov = fov.parse().unwrap();
+    }
+    if let Some(aperture) = matches.value_of("aperture") {
+        scene_desc.camera.aperture = aperture.parse().unwrap();
+    }
+    if let Some(focus_distance) = matches.value_of("focusdist") {
+        scene_desc.camera.focus_dist = focus_distance.parse().unwrap();
+    }
+
+    let mut scene = scene_desc
+        .build(aspect_ratio)
+        .unwrap_or_else(|e| exit_with_error(e));
+
+    println!(
+        "Rendering {} to {}x{} image ({} pixels) with {} bounces/ray and {} samples/pixel",
+        scene_file,
+        image_width,
+        image_height,
+        image_width * image_height,
+        max_depth,
+        samples_per_pixel,
+    );
+
+    // Render
+    scene.set_integrator(integrator);
+    scene.set_sampler(sampler);
+    scene.set_filter(filter);
+    let film = if matches.is_present("adaptive") {
+        let options = AdaptiveOptions {
+            min_samples: matches.value_of("minsamples").unwrap().parse().unwrap(),
+            max_samples: samples_per_pixel,
+            threshold: matches.value_of("threshold").unwrap().parse().unwrap(),
+            ..AdaptiveOptions::default()
+        };
+        scene.render_adaptive(image_width, image_height, options, max_depth)
+    } else {
+        scene.render(image_width, image_height, samples_per_pixel, max_depth)
+    };
+    film.save_with(filename, tone_map.as_ref(), exposure)
+        .unwrap_or_else(|e| exit_with_error(e));
+
+    if let Some(heatmap) = matches.value_of("heatmap") {
+        film.sample_heatmap()
+            .save(heatmap)
+            .unwrap_or_else(|e| exit_with_error(e));
+    }
+}
+
+fn exit_with_error(error: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", error);
+    process::exit(1);
+}
+
+fn match_args() -> ArgMatches<'static> {
+    App::new("Raytracer")
+        .version("0.1.0")
+        .about("Renders a raytraced scene described by a TOML scene file")
+        .arg(
+            Arg::with_name("scene")
+                .value_name("SCENE")
+                .required(true)
+                .index(1)
+                .help("Scene file to render"),
+        )
+        .arg(
+            Arg::with_name("width")
+                .short("w")
+                .long("width")
+                .value_name("WIDTH")
+                .takes_value(true)
+                .required(true)
+                .help("Sets image width of output"),
+        )
+        .arg(
+            Arg::with_name("height")
+                .short("h")
+                .long("height")
+                .value_name("HEIGHT")
+                .takes_value(true)
+                .required(true)
+                .help("Sets image height of output"),
+        )
+        .arg(
+            Arg::with_name("fov")
+                .short("f")
+                .long("fov")
+                .value_name("FOV")
+                .takes_value(true)
+                .help("Overrides the scene's field of vision (fov)"),
+        )
+        .arg(
+            Arg::with_name("aperture")
+                .short("a")
+                .long("aperture")
+                .value_name("APERTURE")
+                .takes_value(true)
+                .help("Overrides the scene's camera aperture (controls amount of defocus blur)"),
+        )
+        .arg(
+            Arg::with_name("focusdist")
+                .short("d")
+                .long("focus-dist")
+                .value_name("FOCUS")
+                .takes_value(true)
+                .help("Overrides the scene's distance to the focus plane"),
+        )
+        .arg(
+            Arg::with_name("samples")
+                .short("s")
+                .long("samples")
+                .value_name("SAMPLES")
+                .takes_value(true)
+                .default_value("500")
+                .help("Sets number of samples per pixel (the most per pixel with --adaptive)"),
+        )
+        .arg(
+            Arg::with_name("adaptive")
+                .long("adaptive")
+                .help("Stops sampling each pixel once its estimated error is below --threshold"),
+        )
+        .arg(
+            Arg::with_name("minsamples")
+                .long("min-samples")
+                .value_name("SAMPLES")
+                .takes_value(true)
+                .default_value("16")
+                .help("Sets number of samples per pixel before adaptive sampling may stop"),
+        )
+        .arg(
+            Arg::with_name("threshold")
+                .long("threshold")
+                .value_name("ERROR")
+                .takes_value(true)
+                .default_value("0.05")
+                .help("Sets relative error at which adaptive sampling stops"),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .long("heatmap")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Saves an image of the number of samples taken in each pixel"),
+        )
+        .arg(
+            Arg::with_name("bounces")
+                .short("b")
+                .long("bounces")
+                .value_name("BOUNCES")
+                .takes_value(true)
+                .default_value("50")
+                .help("Sets max bounces (depth) for each raycast"),
+        )
+        .arg(
+            Arg::with_name("integrator")
+                .long("integrator")
+                .value_name("INTEGRATOR")
+                .takes_value(true)
+                .possible_values(INTEGRATOR_NAMES)
+                .default_value("path")
+                .help("Sets light transport algorithm (ao and normals are for previews and debugging)"),
+        )
+        .arg(
+            Arg::with_name("sampler")
+                .long("sampler")
+                .value_name("SAMPLER")
+                .takes_value(true)
+                .possible_values(SAMPLER_NAMES)
+                .default_value("independent")
+                .help("Sets sample generator for pixel, lens, time, light and BSDF samples"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .default_value("0")
+                .help("Sets random seed; renders with the same seed and options are identical"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .value_name("FILTER")
+                .takes_value(true)
+                .possible_values(FILTER_NAMES)
+                .default_value("box")
+                .help("Sets reconstruction filter samples are splatted onto pixels with"),
+        )
+        .arg(
+            Arg::with_name("filterradius")
+                .long("filter-radius")
+                .value_name("PIXELS")
+                .takes_value(true)
+                .help("Overrides the reconstruction filter's radius"),
+        )
+        .arg(
+            Arg::with_name("tonemap")
+                .short("t")
+                .long("tonemap")
+                .value_name("OPERATOR")
+                .takes_value(true)
+                .possible_values(TONE_MAP_NAMES)
+                .default_value("clamp")
+                .help("Sets tone mapping operator for 8-bit output"),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .short("e")
+                .long("exposure")
+                .value_name("EV")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .default_value("0")
+                .help("Sets exposure compensation in stops for 8-bit output"),
+        )
+        .arg(
+            Arg::with_name("white")
+                .long("white")
+                .value_name("WHITE")
+                .takes_value(true)
+                .help("Sets white point of the reinhard-extended and hable operators"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .takes_value(true)
+                .default_value("render.png")
+                .help("File to save rendered image (.exr and .hdr keep linear radiance)"),
+        )
+        .get_matches()
+}
@@ -1,47 +1,127 @@
+use super::{fresnel_dielectric, to_local, Ggx, GgxDielectricPdf};
 use super::{Material, ScatterRecord};
 use crate::hittable::HitRecord;
-use crate::math::{Ray, Vec3};
-use rand::{thread_rng, Rng};
+use crate::math::{Color, Onb, Ray, Vec3};
+use crate::sampler::Sampler;
+use std::sync::Arc;
 
+/// A transparent material like glass or water, which both reflects and refracts light.
+///
+/// A rough dielectric scatters light through a GGX distribution of microfacets, giving frosted
+/// glass. The interior can absorb light following the Beer-Lambert law, so thick parts of a
+/// tinted object look darker than thin ones. Absorption assumes rays that leave through a
+/// surface entered through the same object's surface, so closed, non-overlapping objects work
+/// best.
 pub struct Dielectric {
     ior: f64,
+    distribution: Ggx,
+    /// Fraction of light absorbed per unit distance inside, for each channel.
+    absorption: Color,
 }
 
 impl Dielectric {
     pub fn new(ior: f64) -> Self {
-        Self { ior }
+        Self {
+            ior,
+            distribution: Ggx::from_roughness(0.0, 0.0),
+            absorption: Color::default(),
+        }
+    }
+
+    /// Sets the roughness of the surface, from 0 for clear glass to 1.
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.distribution = Ggx::from_roughness(roughness, roughness);
+        self
     }
-    fn reflectance(cosine: f64, ior: f64) -> f64 {
-        let mut r0 = (1.0 - ior) / (1.0 + ior);
-        r0 *= r0;
-        r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+
+    /// Tints the interior so that light traveling `distance` through it keeps only `color`.
+    ///
+    /// Panics unless `distance` is positive.
+    pub fn with_absorption(mut self, color: Color, distance: f64) -> Self {
+        assert!(
+            distance > 0.0,
+            "Dielectric absorption distance must be positive"
+        );
+        let coefficient = |c: f64| -c.clamp(1e-6, 1.0).ln() / distance;
+        self.absorption = Color::new(
+            coefficient(color.x()),
+            coefficient(color.y()),
+            coefficient(color.z()),
+        );
+        self
+    }
+
+    /// Fraction of light left after traveling `distance` through the interior.
+    fn transmittance(&self, distance: f64) -> Color {
+        Color::new(
+            (-self.absorption.x() * distance).exp(),
+            (-self.absorption.y() * distance).exp(),
+            (-self.absorption.z() * distance).exp(),
+        )
+    }
+
+    /// Index of refraction of the far side of the surface relative to the side `hit_rec` was
+    /// hit from.
+    fn eta(&self, hit_rec: &HitRecord) -> f64 {
+        if hit_rec.front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        }
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray_in: &Ray, hit_rec: &HitRecord, scatter: &mut ScatterRecord) -> bool {
-        scatter.pdf = None;
-        scatter.attenuation = Vec3::new(1.0, 1.0, 1.0);
-        let refraction_ratio = if hit_rec.front_face {
-            1.0 / self.ior
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        scatter: &mut ScatterRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        // a ray hitting the inside of the surface has traveled through the interior since it
+        // last crossed the surface
+        scatter.attenuation = if hit_rec.front_face {
+            Color::new(1.0, 1.0, 1.0)
         } else {
-            self.ior
+            self.transmittance(hit_rec.t * ray_in.direction().length())
         };
 
         let unit_direction = ray_in.direction().unit();
-        let cos_theta = (1.0 as f64).min((-unit_direction).dot(&hit_rec.normal));
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let eta = self.eta(hit_rec);
 
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        if !self.distribution.is_smooth() {
+            let frame = Onb::new(&hit_rec.normal);
+            let wo = to_local(&frame, &-unit_direction);
+            scatter.specular_ray = None;
+            scatter.pdf = Some(Arc::new(GgxDielectricPdf::new(
+                frame,
+                wo,
+                self.distribution,
+                eta,
+            )));
+            return true;
+        }
+
+        scatter.pdf = None;
+        let cos_theta = (1.0 as f64).min((-unit_direction).dot(&hit_rec.normal));
         let direction: Vec3;
 
-        if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > thread_rng().gen() {
+        // total internal reflection has a reflectance of one
+        if fresnel_dielectric(cos_theta, eta) > sampler.get_1d() {
             direction = unit_direction.reflect(&hit_rec.normal);
         } else {
-            direction = unit_direction.refract(&hit_rec.normal, refraction_ratio);
+            direction = unit_direction.refract(&hit_rec.normal, 1.0 / eta);
         }
 
         scatter.specular_ray = Some(Ray::new(hit_rec.point, direction, ray_in.time()));
         return true;
     }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_rec: &HitRecord, scattered_ray: &Ray) -> f64 {
+        let frame = Onb::new(&hit_rec.normal);
+        let wo = to_local(&frame, &-ray_in.direction().unit());
+        let wi = to_local(&frame, &scattered_ray.direction().unit());
+        self.distribution.dielectric(&wo, &wi, self.eta(hit_rec))
+    }
 }
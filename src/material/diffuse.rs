@@ -30,4 +30,24 @@ impl Material for DiffuseLight {
             self.emit.sample(u, v, p)
         }
     }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+
+    fn average_emitted(&self) -> Color {
+        // average the texture over a grid of uv coordinates
+        let mut sum = Color::default();
+        for i in 0..AVERAGE_GRID {
+            for j in 0..AVERAGE_GRID {
+                let u = (i as f64 + 0.5) / AVERAGE_GRID as f64;
+                let v = (j as f64 + 0.5) / AVERAGE_GRID as f64;
+                sum += self.emit.sample(u, v, &Point3::default());
+            }
+        }
+        sum / (AVERAGE_GRID * AVERAGE_GRID) as f64
+    }
 }
+
+/// Number of rows and columns of texture samples averaged to estimate a light's brightness.
+const AVERAGE_GRID: usize = 8;
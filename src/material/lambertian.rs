@@ -2,6 +2,7 @@ use super::{Material, ScatterRecord};
 use crate::hittable::HitRecord;
 use crate::math::{Ray, Vec3};
 use crate::pdf::CosinePdf;
+use crate::sampler::Sampler;
 use crate::texture::{SolidColor, Texture};
 use std::sync::Arc;
 
@@ -25,7 +26,13 @@ impl From<Arc<dyn Texture>> for Lambertian {
 
 #[allow(unused_variables)]
 impl Material for Lambertian {
-    fn scatter(&self, ray_in: &Ray, hit_rec: &HitRecord, scatter: &mut ScatterRecord) -> bool {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        scatter: &mut ScatterRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
         scatter.attenuation = self.albedo.sample(hit_rec.u, hit_rec.v, &hit_rec.point);
         scatter.pdf = Some(Arc::new(CosinePdf::new(&hit_rec.normal)));
         return true;
@@ -1,31 +1,159 @@
+use super::{fresnel_conductor, fresnel_schlick, shading_frame, to_local, Ggx, GgxReflectionPdf};
 use super::{Material, ScatterRecord};
 use crate::hittable::HitRecord;
-use crate::math::{Ray, Vec3};
+use crate::math::{Color, Ray};
+use crate::sampler::Sampler;
+use std::sync::Arc;
 
+/// Names of the conductors with measured indices of refraction, for [`metal_by_name`].
+pub const METAL_NAMES: &[&str] = &["gold", "copper", "aluminium", "silver"];
+
+/// Creates a metal from one of [`METAL_NAMES`], with the given roughness.
+pub fn metal_by_name(name: &str, roughness: f64) -> Option<Metal> {
+    match name {
+        "gold" => Some(Metal::gold(roughness)),
+        "copper" => Some(Metal::copper(roughness)),
+        "aluminium" => Some(Metal::aluminium(roughness)),
+        "silver" => Some(Metal::silver(roughness)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Fresnel {
+    /// Schlick's approximation from a color seen head-on.
+    Schlick(Color),
+    /// A conductor's complex index of refraction, `eta + ik`.
+    Conductor { eta: Color, k: Color },
+}
+
+/// A metal, reflecting light off a GGX distribution of microfacets.
+///
+/// Roughness runs from 0 for a perfect mirror to 1 for a dull, satin finish, and can differ
+/// along the two tangents of the surface to stretch highlights, like brushed metal. The first
+/// tangent runs the way the surface's `u` texture coordinate increases, such as around a
+/// sphere's vertical axis.
 pub struct Metal {
-    albedo: Vec3,
-    fuzz: f64,
+    fresnel: Fresnel,
+    distribution: Ggx,
 }
 
 impl Metal {
-    pub fn new(albedo: Vec3, fuzz: f64) -> Self {
+    /// Creates a metal reflecting `albedo` when seen head-on, brightening to white at grazing
+    /// angles.
+    pub fn new(albedo: Color, roughness: f64) -> Self {
+        Self {
+            fresnel: Fresnel::Schlick(albedo),
+            distribution: Ggx::from_roughness(roughness, roughness),
+        }
+    }
+
+    /// Creates a metal from its complex index of refraction `eta + ik`, sampled at the red,
+    /// green and blue channels' wavelengths.
+    pub fn conductor(eta: Color, k: Color, roughness: f64) -> Self {
         Self {
-            albedo,
-            fuzz: if fuzz < 1.0 { fuzz } else { 1.0 },
+            fresnel: Fresnel::Conductor { eta, k },
+            distribution: Ggx::from_roughness(roughness, roughness),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::conductor(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::conductor(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::conductor(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::conductor(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    /// Sets separate roughness along the direction of increasing `u` and across it.
+    pub fn with_roughness(mut self, roughness_u: f64, roughness_v: f64) -> Self {
+        self.distribution = Ggx::from_roughness(roughness_u, roughness_v);
+        self
+    }
+
+    fn reflectance(&self, cos_theta: f64) -> Color {
+        match self.fresnel {
+            Fresnel::Schlick(albedo) => fresnel_schlick(albedo, cos_theta),
+            Fresnel::Conductor { eta, k } => fresnel_conductor(cos_theta, eta, k),
         }
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray_in: &Ray, hit_rec: &HitRecord, scatter: &mut ScatterRecord) -> bool {
-        let reflected = ray_in.direction().unit().reflect(&hit_rec.normal);
-        scatter.specular_ray = Some(Ray::new(
-            hit_rec.point,
-            reflected + self.fuzz * Vec3::random_in_unit_sphere(),
-            ray_in.time(),
-        ));
-        scatter.attenuation = self.albedo;
-        scatter.pdf = None;
-        return true;
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        scatter: &mut ScatterRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        let unit_direction = ray_in.direction().unit();
+
+        if self.distribution.is_smooth() {
+            let cos_theta = -unit_direction.dot(&hit_rec.normal);
+            scatter.specular_ray = Some(Ray::new(
+                hit_rec.point,
+                unit_direction.reflect(&hit_rec.normal),
+                ray_in.time(),
+            ));
+            scatter.attenuation = self.reflectance(cos_theta);
+            scatter.pdf = None;
+            return true;
+        }
+
+        // the Fresnel tint depends on the direction light leaves in, so it's left to
+        // `scattering`
+        let frame = shading_frame(hit_rec);
+        let wo = to_local(&frame, &-unit_direction);
+        scatter.specular_ray = None;
+        scatter.attenuation = Color::new(1.0, 1.0, 1.0);
+        scatter.pdf = Some(Arc::new(GgxReflectionPdf::new(
+            frame,
+            wo,
+            self.distribution,
+        )));
+        true
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_rec: &HitRecord, scattered_ray: &Ray) -> f64 {
+        let frame = shading_frame(hit_rec);
+        let wo = to_local(&frame, &-ray_in.direction().unit());
+        let wi = to_local(&frame, &scattered_ray.direction().unit());
+        self.distribution.reflection(&wo, &wi)
+    }
+
+    fn scattering(&self, ray_in: &Ray, hit_rec: &HitRecord, scattered_ray: &Ray) -> Color {
+        let scattering_pdf = self.scattering_pdf(ray_in, hit_rec, scattered_ray);
+        if scattering_pdf <= 0.0 {
+            return Color::default();
+        }
+
+        let wi = scattered_ray.direction().unit();
+        let wh = (wi - ray_in.direction().unit()).unit();
+        self.reflectance(wi.dot(&wh)) * scattering_pdf
     }
 }
@@ -0,0 +1,343 @@
+use crate::hittable::HitRecord;
+use crate::math::{Color, Onb, Vec3};
+use crate::pdf::Pdf;
+use crate::sampler::Sampler;
+use std::f64::consts::PI;
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals, with Smith masking and
+/// shadowing.
+///
+/// Directions are in a local shading frame with the surface normal along +z. `alpha_x` and
+/// `alpha_y` are the roughness along the frame's x and y axes; different values stretch
+/// highlights along the smoother axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl Ggx {
+    /// Below this roughness a surface is treated as a perfect mirror.
+    pub const MIN_ALPHA: f64 = 1e-3;
+
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self { alpha_x, alpha_y }
+    }
+
+    /// Creates a distribution from perceptual roughness in `[0, 1]` along each axis, which is
+    /// squared to give alpha so that roughness changes look roughly even. Unless both axes are
+    /// smooth, neither alpha is left below [`Ggx::MIN_ALPHA`], so a rough surface that is smooth
+    /// along one axis still has a finite density.
+    pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> Self {
+        let alpha = |r: f64| r.clamp(0.0, 1.0).powi(2);
+        let distribution = Self::new(alpha(roughness_x), alpha(roughness_y));
+        if distribution.is_smooth() {
+            return distribution;
+        }
+
+        Self::new(
+            distribution.alpha_x.max(Self::MIN_ALPHA),
+            distribution.alpha_y.max(Self::MIN_ALPHA),
+        )
+    }
+
+    /// Whether the surface is smooth enough to reflect like a mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < Self::MIN_ALPHA
+    }
+
+    /// Density of microfacet normals facing `wh`, per unit of projected area.
+    pub fn d(&self, wh: &Vec3) -> f64 {
+        if wh.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let e = (wh.x() / self.alpha_x).powi(2) + (wh.y() / self.alpha_y).powi(2) + wh.z().powi(2);
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function, the ratio of hidden to visible microfacet area seen from `w`.
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 <= 0.0 {
+            return f64::INFINITY;
+        }
+
+        let tan2_alpha2 = ((self.alpha_x * w.x()).powi(2) + (self.alpha_y * w.y()).powi(2)) / cos2;
+        ((1.0 + tan2_alpha2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of the microfacets facing `wh` that are visible from `w`.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`, using the height-correlated form
+    /// of Smith's function.
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples a microfacet normal from those visible from `wo`, following Heitz, "Sampling the
+    /// GGX Distribution of Visible Normals" (2018).
+    pub fn sample_wh(&self, wo: &Vec3, u: (f64, f64)) -> Vec3 {
+        // stretch the view direction so the distribution becomes a hemisphere
+        let vh = Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit();
+
+        let len2 = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / len2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // a point on the projected hemisphere, squashed towards the visible half
+        let r = u.0.sqrt();
+        let phi = 2.0 * PI * u.1;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // unstretch back to the ellipsoid
+        Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit()
+    }
+
+    /// Density of [`Ggx::sample_wh`] choosing `wh` when seen from `wo`.
+    pub fn pdf_wh(&self, wo: &Vec3, wh: &Vec3) -> f64 {
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * wo.dot(wh).max(0.0) * self.d(wh) / wo.z()
+    }
+
+    /// Light reflected from `wi` towards `wo` by microfacets that are perfect mirrors, before
+    /// Fresnel: the BSDF times the cosine of `wi`.
+    pub fn reflection(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        // D G / (4 cos(theta_o) cos(theta_i)), times cos(theta_i)
+        let wh = (*wo + *wi).unit();
+        self.d(&wh) * self.g(wo, wi) / (4.0 * wo.z())
+    }
+
+    /// Light scattered from `wi` towards `wo` by a rough boundary between dielectrics, with
+    /// `eta` the index of refraction on the far side of the boundary relative to the side `wo`
+    /// is on: the BSDF times the absolute cosine of `wi`, including Fresnel. Follows Walter et
+    /// al., "Microfacet Models for Refraction through Rough Surfaces" (2007).
+    pub fn dielectric(&self, wo: &Vec3, wi: &Vec3, eta: f64) -> f64 {
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return 0.0;
+        }
+
+        if wi.z() > 0.0 {
+            let wh = (*wo + *wi).unit();
+            return fresnel_dielectric(wo.dot(&wh), eta) * self.reflection(wo, wi);
+        }
+
+        let wh = match refraction_half_vector(wo, wi, eta) {
+            Some(wh) => wh,
+            None => return 0.0,
+        };
+        let (o_h, i_h) = (wo.dot(&wh), wi.dot(&wh));
+        let denom = o_h + eta * i_h;
+
+        // radiance is also scaled by 1 / eta^2 as it's squeezed into a narrower cone, which
+        // cancels the eta^2 from the change of variables
+        (1.0 - fresnel_dielectric(o_h, eta)) * self.d(&wh) * self.g(wo, wi) * (i_h * o_h).abs()
+            / (wo.z() * denom * denom)
+    }
+}
+
+/// Samples directions reflected off a GGX surface, by reflecting the view direction about a
+/// visible microfacet normal.
+pub struct GgxReflectionPdf {
+    frame: Onb,
+    wo: Vec3,
+    distribution: Ggx,
+}
+
+impl GgxReflectionPdf {
+    /// `frame` is the shading frame and `wo` the unit direction back towards the viewer, in
+    /// that frame.
+    pub fn new(frame: Onb, wo: Vec3, distribution: Ggx) -> Self {
+        Self {
+            frame,
+            wo,
+            distribution,
+        }
+    }
+}
+
+impl Pdf for GgxReflectionPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        // a reflection can land below the surface, where the material's scattering is zero, but
+        // it's still part of the density samples are drawn with
+        let wi = to_local(&self.frame, &direction.unit());
+        let wh = (self.wo + wi).unit();
+        let o_h = self.wo.dot(&wh);
+        if (self.wo + wi).near_zero() || o_h <= 0.0 {
+            return 0.0;
+        }
+
+        // the reflected direction changes twice as fast as the microfacet normal
+        self.distribution.pdf_wh(&self.wo, &wh) / (4.0 * o_h)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let wh = self.distribution.sample_wh(&self.wo, sampler.get_2d());
+        let wi = (-self.wo).reflect(&wh);
+        self.frame.local_vec(&wi)
+    }
+}
+
+/// Samples directions reflected off or refracted through a rough boundary between dielectrics,
+/// choosing between them by the Fresnel reflectance of a visible microfacet.
+pub struct GgxDielectricPdf {
+    frame: Onb,
+    wo: Vec3,
+    distribution: Ggx,
+    eta: f64,
+}
+
+impl GgxDielectricPdf {
+    /// `frame` is the shading frame, with the normal on the same side as `wo`, the unit
+    /// direction back towards the viewer. `eta` is the index of refraction on the far side of
+    /// the boundary relative to the viewer's side.
+    pub fn new(frame: Onb, wo: Vec3, distribution: Ggx, eta: f64) -> Self {
+        Self {
+            frame,
+            wo,
+            distribution,
+            eta,
+        }
+    }
+}
+
+impl Pdf for GgxDielectricPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        // a microfacet can reflect below the surface or refract above it, so either lobe may
+        // have produced any direction, whichever side of the surface it's on
+        let wi = to_local(&self.frame, &direction.unit());
+        let mut pdf = 0.0;
+
+        let wh = (self.wo + wi).unit();
+        let o_h = self.wo.dot(&wh);
+        if !(self.wo + wi).near_zero() && o_h > 0.0 {
+            pdf += fresnel_dielectric(o_h, self.eta) * self.distribution.pdf_wh(&self.wo, &wh)
+                / (4.0 * o_h);
+        }
+
+        if let Some(wh) = refraction_half_vector(&self.wo, &wi, self.eta) {
+            let (o_h, i_h) = (self.wo.dot(&wh), wi.dot(&wh));
+            let denom = o_h + self.eta * i_h;
+            pdf += (1.0 - fresnel_dielectric(o_h, self.eta))
+                * self.distribution.pdf_wh(&self.wo, &wh)
+                * self.eta
+                * self.eta
+                * i_h.abs()
+                / (denom * denom);
+        }
+
+        pdf
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let wh = self.distribution.sample_wh(&self.wo, sampler.get_2d());
+        let o_h = self.wo.dot(&wh);
+
+        let wi = if sampler.get_1d() < fresnel_dielectric(o_h, self.eta) {
+            (-self.wo).reflect(&wh)
+        } else {
+            (-self.wo).refract(&wh, 1.0 / self.eta)
+        };
+        self.frame.local_vec(&wi)
+    }
+}
+
+/// Microfacet normal that refracts `wo` into `wi`, facing the same side as `wo`, or `None` if
+/// no microfacet could.
+fn refraction_half_vector(wo: &Vec3, wi: &Vec3, eta: f64) -> Option<Vec3> {
+    let wh = -(*wo + eta * *wi);
+    if wh.near_zero() {
+        return None;
+    }
+    let wh = if wh.z() < 0.0 { -wh.unit() } else { wh.unit() };
+
+    // the directions have to be on opposite sides of the microfacet
+    if wo.dot(&wh) <= 0.0 || wi.dot(&wh) >= 0.0 {
+        return None;
+    }
+    Some(wh)
+}
+
+/// The shading frame at a hit, around its normal with the x axis along the surface tangent, so
+/// anisotropic roughness follows the surface rather than the world's axes.
+pub fn shading_frame(hit_rec: &HitRecord) -> Onb {
+    Onb::from_tangent(&hit_rec.normal, &hit_rec.tangent)
+}
+
+/// Expresses a world space vector in `frame`.
+pub fn to_local(frame: &Onb, v: &Vec3) -> Vec3 {
+    Vec3::new(v.dot(&frame.u()), v.dot(&frame.v()), v.dot(&frame.w()))
+}
+
+/// Schlick's approximation of Fresnel reflectance, from the reflectance `f0` at normal
+/// incidence and the cosine of the angle of incidence.
+pub fn fresnel_schlick(f0: Color, cos_theta: f64) -> Color {
+    let m = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + (Color::new(1.0, 1.0, 1.0) - f0) * m
+}
+
+/// Fresnel reflectance of a smooth boundary between dielectrics, for light arriving at an
+/// angle with cosine `cos_theta` from the side with the lower index when `eta`, the relative
+/// index of refraction of the far side, is above one. Total internal reflection gives one.
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction `eta + ik` relative to
+/// the outside medium, for light arriving at an angle with cosine `cos_theta`.
+pub fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    let channel = |eta: f64, k: f64| {
+        let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let (eta2, k2) = (eta * eta, k * k);
+
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta.clamp(0.0, 1.0) * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        0.5 * (rp + rs)
+    };
+
+    Color::new(
+        channel(eta.x(), k.x()),
+        channel(eta.y(), k.y()),
+        channel(eta.z(), k.z()),
+    )
+}
@@ -2,13 +2,20 @@
 #![allow(dead_code)]
 
 use crate::hittable::HitRecord;
-use crate::math::{Color, Point3, Ray};
+use crate::math::{Color, Point3, Ray, Vec3};
 use crate::pdf::Pdf;
+use crate::sampler::Sampler;
 
 /// Scene trait for sampling reflected light.
 #[allow(unused_variables)]
 pub trait Material: Sync + Send {
-    fn scatter(&self, ray_in: &Ray, hit_rec: &HitRecord, scatter: &mut ScatterRecord) -> bool {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        scatter: &mut ScatterRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
         false
     }
 
@@ -16,16 +23,44 @@ pub trait Material: Sync + Send {
         0.0
     }
 
+    /// Like `scattering_pdf`, but per color channel, for materials whose color depends on the
+    /// direction light leaves in, like the Fresnel tint of a metal. The integrator scales it by
+    /// the attenuation from `scatter`.
+    fn scattering(&self, ray_in: &Ray, hit_rec: &HitRecord, scattered_ray: &Ray) -> Color {
+        let pdf = self.scattering_pdf(ray_in, hit_rec, scattered_ray);
+        Color::new(pdf, pdf, pdf)
+    }
+
     #[allow(unused_variables)]
     fn emitted(&self, ray_in: &Ray, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
         Color::default()
     }
+
+    /// Whether surfaces with this material emit light, so the scene samples them as lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Rough average of the radiance emitted over a surface, used to sample brighter lights
+    /// more often.
+    fn average_emitted(&self) -> Color {
+        Color::default()
+    }
+}
+
+/// A material's scattering at one hit, with any textures it depends on already looked up.
+pub trait Bsdf {
+    /// Like [`Material::scattering`], for light leaving the hit along `direction`.
+    fn scattering(&self, direction: &Vec3) -> Color;
 }
 
 pub struct ScatterRecord {
     pub specular_ray: Option<Ray>,
     pub attenuation: Color,
     pub pdf: Option<std::sync::Arc<dyn Pdf>>,
+    /// Left by materials that are costly to evaluate, so every direction the integrator asks
+    /// about reuses the work `scatter` already did.
+    pub bsdf: Option<std::sync::Arc<dyn Bsdf>>,
 }
 
 impl ScatterRecord {
@@ -34,6 +69,22 @@ impl ScatterRecord {
             specular_ray: None,
             attenuation: Color::default(),
             pdf: None,
+            bsdf: None,
+        }
+    }
+
+    /// [`Material::scattering`] of the material that filled in this record, through its
+    /// `bsdf` if it left one.
+    pub fn scattering(
+        &self,
+        material: &dyn Material,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        scattered_ray: &Ray,
+    ) -> Color {
+        match self.bsdf {
+            Some(ref bsdf) => bsdf.scattering(&scattered_ray.direction()),
+            None => material.scattering(ray_in, hit_rec, scattered_ray),
         }
     }
 }
@@ -43,9 +94,13 @@ mod diffuse;
 mod isotropic;
 mod lambertian;
 mod metal;
+mod microfacet;
+mod principled;
 
 pub use dielectric::*;
 pub use diffuse::*;
 pub use isotropic::*;
 pub use lambertian::*;
 pub use metal::*;
+pub use microfacet::*;
+pub use principled::*;
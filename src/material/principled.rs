@@ -0,0 +1,369 @@
+use super::{fresnel_schlick, shading_frame, to_local, Ggx, GgxDielectricPdf, GgxReflectionPdf};
+use super::{Bsdf, Material, ScatterRecord};
+use crate::film::color_luminance;
+use crate::hittable::HitRecord;
+use crate::math::{Color, Onb, Ray, Vec3};
+use crate::pdf::{CosinePdf, Pdf};
+use crate::sampler::Sampler;
+use crate::texture::{SolidColor, Texture};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// How much the sheen takes on the base color rather than staying white.
+const SHEEN_TINT: f64 = 0.5;
+
+/// Roughness of the clear coat, which is always glossy.
+const CLEARCOAT_ROUGHNESS: f64 = 0.1;
+
+/// A physically based "uber" material after Burley, "Physically-Based Shading at Disney"
+/// (2012), blending a rough diffuse base, metal, glass and a clear coat from a handful of
+/// artist-friendly parameters.
+///
+/// Every parameter runs from 0 to 1 and may vary over the surface with a [`Texture`]; scalar
+/// parameters read their texture's first channel.
+///
+/// - `base_color` is the diffuse color, the tint of a metal, or the color of light that has
+///   passed through a glass object, going in and back out.
+/// - `metallic` blends from a dielectric to a conductor.
+/// - `roughness` runs from a mirror to a matte finish, and `anisotropy` stretches highlights
+///   the way the surface's `u` texture coordinate increases.
+/// - `specular` is the strength of a dielectric's reflection, where the default of 0.5 is
+///   about 4%, and `specular_tint` tints it towards the base color.
+/// - `sheen` adds soft, cloth-like reflection at grazing angles.
+/// - `clearcoat` adds a glossy, colorless layer like varnish.
+/// - `transmission` blends a dielectric from opaque to clear glass.
+///
+/// Each lobe is importance sampled on its own, choosing between them by a rough estimate of
+/// how much light each reflects.
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>,
+    specular_tint: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+    anisotropy: Arc<dyn Texture>,
+    ior: f64,
+}
+
+impl Principled {
+    pub const DEFAULT_ROUGHNESS: f64 = 0.5;
+    pub const DEFAULT_SPECULAR: f64 = 0.5;
+    pub const DEFAULT_IOR: f64 = 1.5;
+
+    /// Creates a rough, opaque dielectric with the given base color.
+    pub fn new(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(Self::DEFAULT_ROUGHNESS),
+            specular: constant(Self::DEFAULT_SPECULAR),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            transmission: constant(0.0),
+            anisotropy: constant(0.0),
+            ior: Self::DEFAULT_IOR,
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: Arc<dyn Texture>) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: Arc<dyn Texture>) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_specular_tint(mut self, specular_tint: Arc<dyn Texture>) -> Self {
+        self.specular_tint = specular_tint;
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: Arc<dyn Texture>) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: Arc<dyn Texture>) -> Self {
+        self.clearcoat = clearcoat;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: Arc<dyn Texture>) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: Arc<dyn Texture>) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    /// Sets the index of refraction of the transmissive part, relative to the outside.
+    pub fn with_ior(mut self, ior: f64) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    /// Looks up the parameters where `hit_rec` hit the surface.
+    fn lobes(&self, hit_rec: &HitRecord) -> Lobes {
+        let (u, v, p) = (hit_rec.u, hit_rec.v, &hit_rec.point);
+        let scalar = |texture: &Arc<dyn Texture>| texture.sample(u, v, p).x().clamp(0.0, 1.0);
+
+        let base_color = self.base_color.sample(u, v, p);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let specular = scalar(&self.specular);
+        let specular_tint = scalar(&self.specular_tint);
+        let transmission = scalar(&self.transmission);
+        let anisotropy = scalar(&self.anisotropy);
+
+        // a hue-only version of the base color, for tinting highlights
+        let white = Color::new(1.0, 1.0, 1.0);
+        let base_luminance = color_luminance(base_color);
+        let tint = if base_luminance > 0.0 {
+            base_color / base_luminance
+        } else {
+            white
+        };
+
+        let aspect = (1.0 - 0.9 * anisotropy).sqrt();
+        let alpha = roughness * roughness;
+        let specular_distribution = Ggx::new(
+            (alpha / aspect).max(Ggx::MIN_ALPHA),
+            (alpha * aspect).max(Ggx::MIN_ALPHA),
+        );
+        let clearcoat_alpha = CLEARCOAT_ROUGHNESS * CLEARCOAT_ROUGHNESS;
+
+        let dielectric_specular = 0.08 * specular * lerp(white, tint, specular_tint);
+        Lobes {
+            base_color,
+            // light crosses the surface twice on its way through an object, so each crossing
+            // takes half the tint
+            transmission_color: Color::new(
+                base_color.x().sqrt(),
+                base_color.y().sqrt(),
+                base_color.z().sqrt(),
+            ),
+            roughness,
+            diffuse_weight: (1.0 - metallic) * (1.0 - transmission),
+            sheen: scalar(&self.sheen) * lerp(white, tint, SHEEN_TINT),
+            specular_weight: 1.0 - (1.0 - metallic) * transmission,
+            specular_color: lerp(dielectric_specular, base_color, metallic),
+            specular_distribution,
+            clearcoat_weight: 0.25 * scalar(&self.clearcoat),
+            clearcoat_distribution: Ggx::new(clearcoat_alpha, clearcoat_alpha),
+            transmission_weight: (1.0 - metallic) * transmission,
+            eta: if hit_rec.front_face {
+                self.ior
+            } else {
+                1.0 / self.ior
+            },
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_rec: &HitRecord,
+        scatter: &mut ScatterRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        let bsdf = match PrincipledBsdf::new(self.lobes(hit_rec), ray_in, hit_rec) {
+            Some(bsdf) => Arc::new(bsdf),
+            None => return false,
+        };
+
+        scatter.specular_ray = None;
+        scatter.attenuation = Color::new(1.0, 1.0, 1.0);
+        scatter.pdf = Some(bsdf.clone());
+        scatter.bsdf = Some(bsdf);
+        true
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_rec: &HitRecord, scattered_ray: &Ray) -> f64 {
+        self.scattering(ray_in, hit_rec, scattered_ray).mean()
+    }
+
+    fn scattering(&self, ray_in: &Ray, hit_rec: &HitRecord, scattered_ray: &Ray) -> Color {
+        let frame = shading_frame(hit_rec);
+        let wo = to_local(&frame, &-ray_in.direction().unit());
+        let wi = to_local(&frame, &scattered_ray.direction().unit());
+        self.lobes(hit_rec).evaluate(&wo, &wi)
+    }
+}
+
+/// A [`Principled`] material at one hit, which samples one lobe at a time, chosen by a cheap
+/// estimate of the light each sends back towards the viewer.
+struct PrincipledBsdf {
+    frame: Onb,
+    wo: Vec3,
+    lobes: Lobes,
+    /// Chance of sampling the diffuse, specular, transmission and clear coat lobes.
+    probabilities: [f64; 4],
+    diffuse: CosinePdf,
+    specular: GgxReflectionPdf,
+    transmission: GgxDielectricPdf,
+    clearcoat: GgxReflectionPdf,
+}
+
+impl PrincipledBsdf {
+    /// Returns `None` if no lobe scatters any light.
+    fn new(lobes: Lobes, ray_in: &Ray, hit_rec: &HitRecord) -> Option<Self> {
+        let frame = shading_frame(hit_rec);
+        let wo = to_local(&frame, &-ray_in.direction().unit());
+
+        let weights = [
+            lobes.diffuse_weight * (color_luminance(lobes.base_color) + lobes.sheen.mean()),
+            lobes.specular_weight * fresnel_schlick(lobes.specular_color, wo.z()).mean(),
+            lobes.transmission_weight,
+            lobes.clearcoat_weight * fresnel_schlick(clearcoat_color(), wo.z()).x(),
+        ];
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            frame,
+            wo,
+            probabilities: [
+                weights[0] / total,
+                weights[1] / total,
+                weights[2] / total,
+                weights[3] / total,
+            ],
+            diffuse: CosinePdf::new(&hit_rec.normal),
+            specular: GgxReflectionPdf::new(frame, wo, lobes.specular_distribution),
+            transmission: GgxDielectricPdf::new(frame, wo, lobes.specular_distribution, lobes.eta),
+            clearcoat: GgxReflectionPdf::new(frame, wo, lobes.clearcoat_distribution),
+            lobes,
+        })
+    }
+
+    /// The sampling strategy of each lobe, in the order of `probabilities`.
+    fn pdfs(&self) -> [&dyn Pdf; 4] {
+        [
+            &self.diffuse,
+            &self.specular,
+            &self.transmission,
+            &self.clearcoat,
+        ]
+    }
+}
+
+impl Pdf for PrincipledBsdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.probabilities
+            .iter()
+            .zip(self.pdfs().iter())
+            .filter(|(probability, _)| **probability > 0.0)
+            .map(|(probability, pdf)| probability * pdf.value(direction))
+            .sum()
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let pdfs = self.pdfs();
+        let mut u = sampler.get_1d();
+        for (probability, pdf) in self.probabilities.iter().zip(pdfs.iter()) {
+            if u < *probability {
+                return pdf.generate(sampler);
+            }
+            u -= probability;
+        }
+
+        // rounding left `u` past the last probability, so use the last lobe in use
+        let last = self
+            .probabilities
+            .iter()
+            .rposition(|p| *p > 0.0)
+            .unwrap_or(0);
+        pdfs[last].generate(sampler)
+    }
+}
+
+impl Bsdf for PrincipledBsdf {
+    fn scattering(&self, direction: &Vec3) -> Color {
+        let wi = to_local(&self.frame, &direction.unit());
+        self.lobes.evaluate(&self.wo, &wi)
+    }
+}
+
+/// The parameters of a [`Principled`] material at one point, combined into the weights and
+/// colors of its lobes.
+struct Lobes {
+    base_color: Color,
+    transmission_color: Color,
+    roughness: f64,
+    diffuse_weight: f64,
+    sheen: Color,
+    specular_weight: f64,
+    specular_color: Color,
+    specular_distribution: Ggx,
+    clearcoat_weight: f64,
+    clearcoat_distribution: Ggx,
+    transmission_weight: f64,
+    eta: f64,
+}
+
+impl Lobes {
+    /// The BSDF times the absolute cosine of `wi`, with `wo` and `wi` in the shading frame.
+    fn evaluate(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return Color::default();
+        }
+
+        // only glass lets light through to the other side
+        let transmission =
+            self.transmission_weight * self.specular_distribution.dielectric(wo, wi, self.eta);
+        if wi.z() < 0.0 {
+            return self.transmission_color * transmission;
+        }
+
+        let wh = (*wo + *wi).unit();
+        let cos_d = wi.dot(&wh).clamp(0.0, 1.0);
+
+        // Burley's diffuse, which darkens smooth surfaces and brightens rough ones at grazing
+        // angles, plus a sheen that rises towards grazing
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = |cos: f64| 1.0 + (fd90 - 1.0) * (1.0 - cos).powi(5);
+        let diffuse =
+            self.base_color * (fd(wo.z()) * fd(wi.z()) / PI) + self.sheen * (1.0 - cos_d).powi(5);
+
+        let specular = fresnel_schlick(self.specular_color, cos_d)
+            * self.specular_distribution.reflection(wo, wi);
+        let clearcoat = fresnel_schlick(clearcoat_color(), cos_d)
+            * self.clearcoat_distribution.reflection(wo, wi);
+
+        self.diffuse_weight * wi.z() * diffuse
+            + self.specular_weight * specular
+            + self.clearcoat_weight * clearcoat
+            + Color::new(transmission, transmission, transmission)
+    }
+}
+
+/// Reflectance of the clear coat seen head-on, that of a varnish with an index of refraction
+/// of 1.5.
+fn clearcoat_color() -> Color {
+    Color::new(0.04, 0.04, 0.04)
+}
+
+fn constant(value: f64) -> Arc<dyn Texture> {
+    Arc::new(SolidColor::new(Color::new(value, value, value)))
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
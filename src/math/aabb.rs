@@ -19,6 +19,15 @@ impl Aabb {
         self.max
     }
 
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
     pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
         let (r_origin, r_direction) = (ray.origin(), ray.direction());
         for a in 0..3 {
@@ -0,0 +1,135 @@
+/// A piecewise-constant distribution over `[0, 1)`, for sampling points in proportion to a
+/// tabulated function.
+///
+/// Negative values are treated as zero. A function that is zero everywhere is sampled
+/// uniformly instead.
+#[derive(Debug, Clone)]
+pub struct Distribution1d {
+    func: Vec<f64>,
+    /// Running integral of `func`, normalized to end at one, with a leading zero.
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1d {
+    /// Panics if `func` is empty.
+    pub fn new(func: Vec<f64>) -> Self {
+        assert!(
+            !func.is_empty(),
+            "Distribution1d requires at least one value"
+        );
+
+        let func: Vec<f64> = func.into_iter().map(|f| f.max(0.0)).collect();
+        let n = func.len() as f64;
+
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(0.0);
+        for f in func.iter() {
+            cdf.push(cdf[cdf.len() - 1] + f / n);
+        }
+
+        let integral = cdf[func.len()];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            *c = if integral > 0.0 {
+                *c / integral
+            } else {
+                i as f64 / n
+            };
+        }
+
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// Integral of the function over `[0, 1)`.
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps a uniform sample to a point, returning the point, its density and the index of the
+    /// segment it falls in.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        let index = (self.cdf.partition_point(|&c| c <= u) - 1).min(self.len() - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+        let x = ((index as f64 + offset) / self.len() as f64).min(1.0 - f64::EPSILON);
+
+        (x, self.segment_pdf(index), index)
+    }
+
+    /// Density of [`Distribution1d::sample`] returning `x`.
+    pub fn pdf(&self, x: f64) -> f64 {
+        self.segment_pdf(self.index(x))
+    }
+
+    /// Index of the segment `x` falls in.
+    pub fn index(&self, x: f64) -> usize {
+        ((x * self.len() as f64) as usize).min(self.len() - 1)
+    }
+
+    fn segment_pdf(&self, index: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.func[index] / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A piecewise-constant distribution over `[0, 1)²`, sampled by choosing a row from the
+/// marginal distribution of the rows and then a point within it.
+#[derive(Debug, Clone)]
+pub struct Distribution2d {
+    rows: Vec<Distribution1d>,
+    marginal: Distribution1d,
+}
+
+impl Distribution2d {
+    /// Creates a distribution from `func`, a row-major table with `width` columns. `u`
+    /// coordinates run along rows and `v` coordinates down columns.
+    ///
+    /// Panics if `func` is empty or isn't a whole number of rows.
+    pub fn new(func: &[f64], width: usize) -> Self {
+        assert!(
+            width > 0 && !func.is_empty() && func.len().is_multiple_of(width),
+            "Distribution2d requires a whole number of rows"
+        );
+
+        let rows: Vec<Distribution1d> = func
+            .chunks(width)
+            .map(|row| Distribution1d::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1d::new(rows.iter().map(|row| row.integral()).collect());
+
+        Self { rows, marginal }
+    }
+
+    /// Maps a uniform 2D sample to a point `(u, v)`, returning it with its density.
+    pub fn sample(&self, u: (f64, f64)) -> ((f64, f64), f64) {
+        let (v, v_pdf, row) = self.marginal.sample(u.1);
+        let (u, u_pdf, _) = self.rows[row].sample(u.0);
+        ((u, v), u_pdf * v_pdf)
+    }
+
+    /// Density of [`Distribution2d::sample`] returning `(u, v)`.
+    pub fn pdf(&self, (u, v): (f64, f64)) -> f64 {
+        let row = self.marginal.index(v);
+        self.rows[row].pdf(u) * self.marginal.pdf(v)
+    }
+}
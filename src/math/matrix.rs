@@ -0,0 +1,198 @@
+use crate::math::{Point3, Vec3};
+use std::ops::Mul;
+
+/// A 4x4 row-major matrix for affine transforms of points, vectors and normals.
+///
+/// Matrices compose right-to-left: `a * b` applies `b` first, then `a`. Rotation angles are
+/// given in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(scale: Vec3) -> Self {
+        Self::new([
+            [scale.x(), 0.0, 0.0, 0.0],
+            [0.0, scale.y(), 0.0, 0.0],
+            [0.0, 0.0, scale.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_x(angle: f64) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_y(angle: f64) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self::new([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_z(angle: f64) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self::new([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation by `angle` degrees counter-clockwise about an arbitrary `axis`.
+    pub fn rotation(axis: Vec3, angle: f64) -> Self {
+        let a = axis.unit();
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        Self::new([
+            [
+                t * a.x() * a.x() + cos,
+                t * a.x() * a.y() - sin * a.z(),
+                t * a.x() * a.z() + sin * a.y(),
+                0.0,
+            ],
+            [
+                t * a.x() * a.y() + sin * a.z(),
+                t * a.y() * a.y() + cos,
+                t * a.y() * a.z() - sin * a.x(),
+                0.0,
+            ],
+            [
+                t * a.x() * a.z() - sin * a.y(),
+                t * a.y() * a.z() + sin * a.x(),
+                t * a.z() * a.z() + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self::new(m)
+    }
+
+    /// Determinant of the upper-left 3x3 (linear) part of the matrix.
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Inverse of an affine matrix, or `None` if it is singular or not finite.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.m;
+        let det = self.determinant3();
+
+        // compare against the determinant of a uniform scale by the longest row, so the test
+        // works the same for matrices at any scale
+        let row_length =
+            |row: &[f64; 4]| (row[0] * row[0] + row[1] * row[1] + row[2] * row[2]).sqrt();
+        let scale = row_length(&m[0])
+            .max(row_length(&m[1]))
+            .max(row_length(&m[2]));
+        if !det.is_finite() || det.abs() <= 1e-12 * scale.powi(3) {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+
+        // inverse of the linear part from its adjugate
+        let mut inv = [[0.0; 4]; 4];
+        inv[0][0] = (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det;
+        inv[0][1] = (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det;
+        inv[0][2] = (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det;
+        inv[1][0] = (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det;
+        inv[1][1] = (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det;
+        inv[1][2] = (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det;
+        inv[2][0] = (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det;
+        inv[2][1] = (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det;
+        inv[2][2] = (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det;
+
+        // undo the translation in the rotated frame
+        for row in inv.iter_mut().take(3) {
+            row[3] = -(row[0] * m[0][3] + row[1] * m[1][3] + row[2] * m[2][3]);
+        }
+        inv[3][3] = 1.0;
+
+        Some(Self::new(inv))
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        Point3::new(
+            m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3],
+            m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3],
+            m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3],
+        )
+    }
+
+    /// Transforms a direction, ignoring the translation part of the matrix.
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Self::new(m)
+    }
+}
@@ -3,12 +3,16 @@
 //! These types are purely mathematical, and do not implement high-level scene abstractions such as [`Hittable`](crate::hittable::Hittable).
 
 mod aabb;
+mod distribution;
+mod matrix;
 mod onb;
 mod ray;
 mod sphere;
 mod vec3;
 
 pub use aabb::*;
+pub use distribution::*;
+pub use matrix::*;
 pub use onb::*;
 pub use ray::*;
 pub use sphere::*;
@@ -1,6 +1,7 @@
 use super::Vec3;
 use std::ops::Index;
 
+#[derive(Debug, Clone, Copy)]
 pub struct Onb {
     axis: [Vec3; 3],
 }
@@ -19,6 +20,21 @@ impl Onb {
         Self { axis }
     }
 
+    /// Creates a basis around `n` with its first axis along the part of `tangent`
+    /// perpendicular to `n`, or an arbitrary first axis if `tangent` has no such part.
+    pub fn from_tangent(n: &Vec3, tangent: &Vec3) -> Self {
+        let w = n.unit();
+        let u = *tangent - w * w.dot(tangent);
+        if u.length_squared() <= 1e-12 * tangent.length_squared() {
+            return Self::new(n);
+        }
+
+        let u = u.unit();
+        Self {
+            axis: [u, w.cross(&u), w],
+        }
+    }
+
     pub fn u(&self) -> Vec3 {
         self[0]
     }
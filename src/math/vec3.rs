@@ -33,6 +33,16 @@ impl Vec3 {
         (self[0].abs() < s) && (self[1].abs() < s) && (self[2].abs() < s)
     }
 
+    /// Largest of the three components.
+    pub fn max_component(&self) -> f64 {
+        self[0].max(self[1]).max(self[2])
+    }
+
+    /// Average of the three components.
+    pub fn mean(&self) -> f64 {
+        (self[0] + self[1] + self[2]) / 3.0
+    }
+
     pub fn length(&self) -> f64 {
         let length_squared: f64 = self.length_squared();
         length_squared.sqrt()
@@ -76,12 +86,21 @@ impl Vec3 {
     }
 
     pub fn random() -> Self {
-        let mut rng = thread_rng();
-        Self::new(rng.gen(), rng.gen(), rng.gen())
+        Self::random_with(&mut thread_rng())
     }
 
     pub fn random_in_range(min: f64, max: f64) -> Self {
-        let mut rng = thread_rng();
+        Self::random_in_range_with(&mut thread_rng(), min, max)
+    }
+
+    /// Like [`Vec3::random`], drawing from `rng` so results can be reproduced with a seeded
+    /// generator.
+    pub fn random_with<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::new(rng.gen(), rng.gen(), rng.gen())
+    }
+
+    /// Like [`Vec3::random_in_range`], drawing from `rng`.
+    pub fn random_in_range_with<R: Rng + ?Sized>(rng: &mut R, min: f64, max: f64) -> Self {
         Self::new(
             rng.gen_range(min, max),
             rng.gen_range(min, max),
@@ -125,8 +144,17 @@ impl Vec3 {
 
     pub fn random_cosine_direction() -> Self {
         let mut rng = thread_rng();
-        let r1: f64 = rng.gen();
-        let r2: f64 = rng.gen();
+        Self::cosine_direction((rng.gen(), rng.gen()))
+    }
+
+    pub fn random_to_sphere(radius: f64, dist_squared: f64) -> Self {
+        let mut rng = thread_rng();
+        Self::to_sphere(radius, dist_squared, (rng.gen(), rng.gen()))
+    }
+
+    /// Maps a uniform 2D sample to a cosine-weighted direction around +z.
+    pub fn cosine_direction(u: (f64, f64)) -> Self {
+        let (r1, r2) = u;
         let z = (1.0 - r2).sqrt();
 
         let phi = 2.0 * std::f64::consts::PI * r1;
@@ -136,10 +164,10 @@ impl Vec3 {
         Vec3::new(x, y, z)
     }
 
-    pub fn random_to_sphere(radius: f64, dist_squared: f64) -> Self {
-        let mut rng = thread_rng();
-        let r1: f64 = rng.gen();
-        let r2: f64 = rng.gen();
+    /// Maps a uniform 2D sample to a direction around +z towards a sphere of `radius` whose
+    /// center is at squared distance `dist_squared`, uniform over the sphere's solid angle.
+    pub fn to_sphere(radius: f64, dist_squared: f64, u: (f64, f64)) -> Self {
+        let (r1, r2) = u;
         let z = 1.0 + r2 * ((1.0 - radius * radius / dist_squared).sqrt() - 1.0);
 
         let phi = 2.0 * std::f64::consts::PI * r1;
@@ -148,6 +176,47 @@ impl Vec3 {
 
         Vec3::new(x, y, z)
     }
+
+    /// Maps a uniform 2D sample to a direction around +z, uniform over the cone of directions
+    /// within an angle of `acos(cos_theta_max)` of the axis.
+    pub fn in_cone(cos_theta_max: f64, u: (f64, f64)) -> Self {
+        let (r1, r2) = u;
+        let z = 1.0 - r2 * (1.0 - cos_theta_max);
+
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+
+        Vec3::new(phi.cos() * radius, phi.sin() * radius, z)
+    }
+
+    /// Maps a uniform 2D sample to a point in the unit disk in the xy plane, using Shirley's
+    /// concentric mapping to keep neighbouring samples close.
+    pub fn in_unit_disk(u: (f64, f64)) -> Self {
+        let a = 2.0 * u.0 - 1.0;
+        let b = 2.0 * u.1 - 1.0;
+        if a == 0.0 && b == 0.0 {
+            return Self::default();
+        }
+
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, std::f64::consts::FRAC_PI_4 * (b / a))
+        } else {
+            (
+                b,
+                std::f64::consts::FRAC_PI_2 - std::f64::consts::FRAC_PI_4 * (a / b),
+            )
+        };
+        Self::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
+
+    /// Maps a uniform 2D direction sample and a uniform radius sample to a point in the unit
+    /// ball.
+    pub fn in_unit_sphere(u: (f64, f64), r: f64) -> Self {
+        let z = 1.0 - 2.0 * u.0;
+        let radius = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * u.1;
+        r.cbrt() * Self::new(radius * phi.cos(), radius * phi.sin(), z)
+    }
 }
 
 impl Default for Vec3 {
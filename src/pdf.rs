@@ -1,11 +1,11 @@
 use crate::hittable::*;
 use crate::math::{Onb, Point3, Vec3};
-use rand::{thread_rng, Rng};
+use crate::sampler::Sampler;
 use std::sync::Arc;
 
 pub trait Pdf {
     fn value(&self, direction: &Vec3) -> f64;
-    fn generate(&self) -> Vec3;
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3;
 }
 
 pub struct CosinePdf {
@@ -27,8 +27,9 @@ impl Pdf for CosinePdf {
         }
     }
 
-    fn generate(&self) -> Vec3 {
-        self.uvw.local_vec(&Vec3::random_cosine_direction())
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.uvw
+            .local_vec(&Vec3::cosine_direction(sampler.get_2d()))
     }
 }
 
@@ -48,8 +49,8 @@ impl Pdf for HittablePdf {
         self.hittable.pdf_value(&self.o, direction)
     }
 
-    fn generate(&self) -> Vec3 {
-        self.hittable.random(&self.o)
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.hittable.random(&self.o, sampler)
     }
 }
 
@@ -68,11 +69,11 @@ impl Pdf for MixturePdf {
         0.5 * self.pdfs[0].value(direction) + 0.5 * self.pdfs[1].value(direction)
     }
 
-    fn generate(&self) -> Vec3 {
-        if thread_rng().gen::<f64>() < 0.5 {
-            self.pdfs[0].generate()
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        if sampler.get_1d() < 0.5 {
+            self.pdfs[0].generate(sampler)
         } else {
-            self.pdfs[1].generate()
+            self.pdfs[1].generate(sampler)
         }
     }
 }
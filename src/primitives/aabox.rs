@@ -1,5 +1,6 @@
 use super::{XyPlane, XzPlane, YzPlane};
 use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::light::LightList;
 use crate::material::Material;
 use crate::math::{Aabb, Point3, Ray};
 use std::sync::Arc;
@@ -76,4 +77,13 @@ impl Hittable for AaBox {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         self.sides.hit(ray, t_min, t_max)
     }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        self.sides.add_lights(this, material, lights);
+    }
 }
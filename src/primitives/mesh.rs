@@ -0,0 +1,262 @@
+use super::triangle::{
+    intersect_triangle, triangle_area, triangle_bounding_box, uniform_barycentric,
+};
+use crate::bvh::{BvhNode, SahOptions};
+use crate::material::Material;
+use crate::math::Ray;
+use crate::{
+    hittable::{HitRecord, Hittable},
+    light::LightList,
+    math::{Aabb, Point3, Vec3},
+    sampler::Sampler,
+};
+use std::sync::Arc;
+
+/// Vertex and index buffers shared by every face of a [`TriangleMesh`].
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    indices: Vec<[usize; 3]>,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl MeshData {
+    fn vertices(&self, face: usize) -> (&Point3, &Point3, &Point3) {
+        let [i0, i1, i2] = self.indices[face];
+        (
+            &self.positions[i0],
+            &self.positions[i1],
+            &self.positions[i2],
+        )
+    }
+}
+
+/// A single face of a [`TriangleMesh`], referencing the mesh's shared buffers.
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (p0, p1, p2) = self.mesh.vertices(self.face);
+        let (t, b1, b2) = intersect_triangle(p0, p1, p2, ray, t_min, t_max)?;
+        let b0 = 1.0 - b1 - b2;
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+
+        let mut geometric_normal = (*p1 - *p0).cross(&(*p2 - *p0)).unit();
+
+        // interpolate shading normal if the mesh has per-vertex normals
+        let shading_normal = if self.mesh.normals.is_empty() {
+            geometric_normal
+        } else {
+            let n = b0 * self.mesh.normals[i0]
+                + b1 * self.mesh.normals[i1]
+                + b2 * self.mesh.normals[i2];
+            if n.near_zero() {
+                geometric_normal
+            } else {
+                n.unit()
+            }
+        };
+
+        // keep the geometric normal on the same side as the shading normal so that
+        // inconsistent winding in the index buffer does not flip front faces
+        if geometric_normal.dot(&shading_normal) < 0.0 {
+            geometric_normal = -geometric_normal;
+        }
+
+        let (u, v, tangent) = if self.mesh.uvs.is_empty() {
+            (b1, b2, *p1 - *p0)
+        } else {
+            let (u0, v0) = self.mesh.uvs[i0];
+            let (u1, v1) = self.mesh.uvs[i1];
+            let (u2, v2) = self.mesh.uvs[i2];
+
+            // solve p - p2 = (u - u2) dp/du + (v - v2) dp/dv over the face's edges, falling
+            // back to the first edge where the uvs are degenerate
+            let (du02, dv02, du12, dv12) = (u0 - u2, v0 - v2, u1 - u2, v1 - v2);
+            let determinant = du02 * dv12 - dv02 * du12;
+            let tangent = if determinant.abs() < 1e-12 {
+                *p1 - *p0
+            } else {
+                (dv12 * (*p0 - *p2) - dv02 * (*p1 - *p2)) / determinant
+            };
+
+            (
+                b0 * u0 + b1 * u1 + b2 * u2,
+                b0 * v0 + b1 * v1 + b2 * v2,
+                tangent,
+            )
+        };
+
+        let mut hit_rec = HitRecord {
+            t,
+            point: ray.at(t),
+            normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            tangent,
+            front_face: false,
+            material: self.mesh.material.clone(),
+            u,
+            v,
+        };
+        hit_rec.set_face_normal(ray, &geometric_normal);
+        hit_rec.normal = if hit_rec.front_face {
+            shading_normal
+        } else {
+            -shading_normal
+        };
+
+        Some(hit_rec)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        let (p0, p1, p2) = self.mesh.vertices(self.face);
+        Some(triangle_bounding_box(p0, p1, p2))
+    }
+}
+
+/// An indexed triangle mesh.
+///
+/// Faces share the mesh's position, normal and texture coordinate buffers, so a mesh with
+/// thousands of faces only stores each vertex once. Normals and UVs are optional; when present
+/// they must have one entry per position and are interpolated across each face.
+pub struct TriangleMesh {
+    mesh: Arc<MeshData>,
+    bvh: BvhNode,
+    area_cdf: Vec<f64>,
+    area: f64,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+        material: Option<Arc<dyn Material>>,
+    ) -> Self {
+        assert!(
+            !indices.is_empty(),
+            "TriangleMesh requires at least one face"
+        );
+        assert!(
+            normals.is_empty() || normals.len() == positions.len(),
+            "TriangleMesh normals must match positions"
+        );
+        assert!(
+            uvs.is_empty() || uvs.len() == positions.len(),
+            "TriangleMesh uvs must match positions"
+        );
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&index| index < positions.len()),
+            "TriangleMesh indices must refer to positions"
+        );
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            indices,
+            material,
+        });
+
+        // cumulative face areas for uniform area sampling
+        let mut area_cdf = Vec::with_capacity(mesh.indices.len());
+        let mut area = 0.0;
+        for face in 0..mesh.indices.len() {
+            let (p0, p1, p2) = mesh.vertices(face);
+            area += triangle_area(p0, p1, p2);
+            area_cdf.push(area);
+        }
+
+        let faces: Vec<Arc<dyn Hittable>> = (0..mesh.indices.len())
+            .map(|face| {
+                Arc::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    face,
+                }) as Arc<dyn Hittable>
+            })
+            .collect();
+        let bvh = BvhNode::new_sah(&faces, 0.0, 1.0, SahOptions::default());
+
+        Self {
+            mesh,
+            bvh,
+            area_cdf,
+            area,
+        }
+    }
+
+    /// Number of faces in the mesh.
+    pub fn len(&self) -> usize {
+        self.mesh.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mesh.indices.is_empty()
+    }
+
+    /// Total surface area of all faces.
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.bvh.bounding_box(t0, t1)
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        // area sampling may pick any face the ray passes through, so sum the solid angle
+        // density over every intersection along the ray
+        let ray = Ray::new(*o, *v, 0.0);
+        let mut t_min = 0.001;
+        let mut pdf = 0.0;
+
+        while let Some(hit_rec) = self.bvh.hit(&ray, t_min, f64::INFINITY) {
+            let dist_squared = hit_rec.t * hit_rec.t * v.length_squared();
+            let cosine = v.dot(&hit_rec.geometric_normal).abs() / v.length();
+            if cosine > 0.0 {
+                pdf += dist_squared / (cosine * self.area);
+            }
+            t_min = hit_rec.t + 0.001;
+        }
+
+        pdf
+    }
+
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let target = sampler.get_1d() * self.area;
+        let face = self
+            .area_cdf
+            .partition_point(|&a| a < target)
+            .min(self.area_cdf.len() - 1);
+
+        let (p0, p1, p2) = self.mesh.vertices(face);
+        let (u1, u2) = sampler.get_2d();
+        let (b0, b1, b2) = uniform_barycentric(u1, u2);
+        b0 * *p0 + b1 * *p1 + b2 * *p2 - *o
+    }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(material) = material.or(self.mesh.material.as_ref()) {
+            lights.add_surface(this.clone(), material.as_ref(), self.area);
+        }
+    }
+}
@@ -1,11 +1,15 @@
 //! Scene object types that implement [`Hittable`](crate::hittable::Hittable)
 
 mod aabox;
+mod mesh;
 mod plane;
 mod sphere;
+mod triangle;
 mod volume;
 
 pub use aabox::*;
+pub use mesh::*;
 pub use plane::*;
 pub use sphere::*;
+pub use triangle::*;
 pub use volume::*;
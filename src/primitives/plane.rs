@@ -2,9 +2,10 @@ use crate::material::Material;
 use crate::math::Ray;
 use crate::{
     hittable::{HitRecord, Hittable},
+    light::LightList,
     math::{Aabb, Point3, Vec3},
+    sampler::Sampler,
 };
-use rand::{thread_rng, Rng};
 use std::sync::Arc;
 pub struct XyPlane {
     x0: f64,
@@ -53,6 +54,7 @@ impl Hittable for XyPlane {
         let mut hit_rec = HitRecord::default();
 
         hit_rec.u = (x - self.x0) / (self.x1 - self.x0);
+        hit_rec.tangent = Vec3::new(1.0, 0.0, 0.0);
         hit_rec.v = (y - self.y0) / (self.y1 - self.y0);
         hit_rec.t = t;
 
@@ -72,6 +74,40 @@ impl Hittable for XyPlane {
             Point3::new(self.x1, self.y1, self.k + offset),
         ))
     }
+
+    fn pdf_value(&self, origin: &Point3, v: &Vec3) -> f64 {
+        if let Some(hit_rec) = self.hit(&Ray::new(*origin, *v, 0.0), 0.001, f64::INFINITY) {
+            let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+            let dist_squared = hit_rec.t * hit_rec.t * v.length_squared();
+            let cosine = v.dot(&hit_rec.normal).abs() / v.length();
+
+            dist_squared / (cosine * area)
+        } else {
+            0.0
+        }
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u1, u2) = sampler.get_2d();
+        let random_point = Point3::new(
+            self.x0 + u1 * (self.x1 - self.x0),
+            self.y0 + u2 * (self.y1 - self.y0),
+            self.k,
+        );
+        random_point - *origin
+    }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(material) = material.or(self.material.as_ref()) {
+            let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+            lights.add_surface(this.clone(), material.as_ref(), area);
+        }
+    }
 }
 
 pub struct XzPlane {
@@ -121,6 +157,7 @@ impl Hittable for XzPlane {
         let mut hit_rec = HitRecord::default();
 
         hit_rec.u = (x - self.x0) / (self.x1 - self.x0);
+        hit_rec.tangent = Vec3::new(1.0, 0.0, 0.0);
         hit_rec.v = (z - self.z0) / (self.z1 - self.z0);
         hit_rec.t = t;
 
@@ -153,15 +190,27 @@ impl Hittable for XzPlane {
         }
     }
 
-    fn random(&self, origin: &Vec3) -> Vec3 {
-        let mut rng = thread_rng();
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u1, u2) = sampler.get_2d();
         let random_point = Point3::new(
-            rng.gen_range(self.x0, self.x1),
+            self.x0 + u1 * (self.x1 - self.x0),
             self.k,
-            rng.gen_range(self.z0, self.z1),
+            self.z0 + u2 * (self.z1 - self.z0),
         );
         random_point - *origin
     }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(material) = material.or(self.material.as_ref()) {
+            let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+            lights.add_surface(this.clone(), material.as_ref(), area);
+        }
+    }
 }
 
 pub struct YzPlane {
@@ -211,6 +260,7 @@ impl Hittable for YzPlane {
         let mut hit_rec = HitRecord::default();
 
         hit_rec.u = (y - self.y0) / (self.y1 - self.y0);
+        hit_rec.tangent = Vec3::new(0.0, 1.0, 0.0);
         hit_rec.v = (z - self.z0) / (self.z1 - self.z0);
         hit_rec.t = t;
 
@@ -230,4 +280,38 @@ impl Hittable for YzPlane {
             Point3::new(self.k + offset, self.y1, self.z1),
         ))
     }
+
+    fn pdf_value(&self, origin: &Point3, v: &Vec3) -> f64 {
+        if let Some(hit_rec) = self.hit(&Ray::new(*origin, *v, 0.0), 0.001, f64::INFINITY) {
+            let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+            let dist_squared = hit_rec.t * hit_rec.t * v.length_squared();
+            let cosine = v.dot(&hit_rec.normal).abs() / v.length();
+
+            dist_squared / (cosine * area)
+        } else {
+            0.0
+        }
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u1, u2) = sampler.get_2d();
+        let random_point = Point3::new(
+            self.k,
+            self.y0 + u1 * (self.y1 - self.y0),
+            self.z0 + u2 * (self.z1 - self.z0),
+        );
+        random_point - *origin
+    }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(material) = material.or(self.material.as_ref()) {
+            let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+            lights.add_surface(this.clone(), material.as_ref(), area);
+        }
+    }
 }
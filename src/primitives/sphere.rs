@@ -2,7 +2,9 @@ use crate::material::Material;
 use crate::math::Ray;
 use crate::{
     hittable::{HitRecord, Hittable},
+    light::LightList,
     math::{Aabb, Onb, Point3, Vec3},
+    sampler::Sampler,
 };
 use std::sync::Arc;
 
@@ -35,6 +37,11 @@ impl Sphere {
         let phi = neg_p.z().atan2(p.x()) + PI;
         (phi / (2.0 * PI), theta / PI)
     }
+
+    // Direction in which u increases at a point p on the unit sphere, circling the Y axis.
+    fn tangent(p: &Point3) -> Vec3 {
+        Vec3::new(p.z(), 0.0, -p.x())
+    }
 }
 
 impl Hittable for Sphere {
@@ -66,6 +73,8 @@ impl Hittable for Sphere {
             t: root,
             point: hit_p,
             normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            tangent: Sphere::tangent(&outward_normal),
             front_face: false,
             material: self.material.clone(),
             u,
@@ -95,11 +104,27 @@ impl Hittable for Sphere {
             0.0
         }
     }
-    fn random(&self, o: &Vec3) -> Vec3 {
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
         let direction: Vec3 = self.center - *o;
         let dist_squared = direction.length_squared();
         let uvw = Onb::new(&direction);
-        uvw.local_vec(&Vec3::random_to_sphere(self.radius, dist_squared))
+        uvw.local_vec(&Vec3::to_sphere(
+            self.radius,
+            dist_squared,
+            sampler.get_2d(),
+        ))
+    }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(material) = material.or(self.material.as_ref()) {
+            let area = 4.0 * std::f64::consts::PI * self.radius * self.radius;
+            lights.add_surface(this.clone(), material.as_ref(), area);
+        }
     }
 }
 
@@ -165,6 +190,8 @@ impl Hittable for MovingSphere {
             t: root,
             point: hit_p,
             normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            tangent: Sphere::tangent(&outward_normal),
             front_face: false,
             material: self.material.clone(),
             u,
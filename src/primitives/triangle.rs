@@ -0,0 +1,154 @@
+use crate::material::Material;
+use crate::math::Ray;
+use crate::{
+    hittable::{HitRecord, Hittable},
+    light::LightList,
+    math::{Aabb, Point3, Vec3},
+    sampler::Sampler,
+};
+use std::sync::Arc;
+
+/// A single triangle defined by three vertices in counter-clockwise order.
+pub struct Triangle {
+    p0: Point3,
+    p1: Point3,
+    p2: Point3,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Triangle {
+    pub fn new(p0: Point3, p1: Point3, p2: Point3, material: Option<Arc<dyn Material>>) -> Self {
+        Self {
+            p0,
+            p1,
+            p2,
+            material,
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        triangle_area(&self.p0, &self.p1, &self.p2)
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect_triangle(&self.p0, &self.p1, &self.p2, ray, t_min, t_max)?;
+
+        let outward_normal = (self.p1 - self.p0).cross(&(self.p2 - self.p0)).unit();
+        let mut hit_rec = HitRecord {
+            t,
+            point: ray.at(t),
+            normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            tangent: self.p1 - self.p0,
+            front_face: false,
+            material: self.material.clone(),
+            u: b1,
+            v: b2,
+        };
+        hit_rec.set_face_normal(ray, &outward_normal);
+
+        Some(hit_rec)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(triangle_bounding_box(&self.p0, &self.p1, &self.p2))
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3) -> f64 {
+        if let Some(hit_rec) = self.hit(&Ray::new(*o, *v, 0.0), 0.001, f64::INFINITY) {
+            let dist_squared = hit_rec.t * hit_rec.t * v.length_squared();
+            let cosine = v.dot(&hit_rec.normal).abs() / v.length();
+
+            dist_squared / (cosine * self.area())
+        } else {
+            0.0
+        }
+    }
+
+    fn random(&self, o: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u1, u2) = sampler.get_2d();
+        let (b0, b1, b2) = uniform_barycentric(u1, u2);
+        b0 * self.p0 + b1 * self.p1 + b2 * self.p2 - *o
+    }
+
+    fn add_lights(
+        &self,
+        this: &Arc<dyn Hittable>,
+        material: Option<&Arc<dyn Material>>,
+        lights: &mut LightList,
+    ) {
+        if let Some(material) = material.or(self.material.as_ref()) {
+            lights.add_surface(this.clone(), material.as_ref(), self.area());
+        }
+    }
+}
+
+/// Möller-Trumbore ray/triangle intersection.
+///
+/// Returns the ray parameter `t` and the barycentric weights of `p1` and `p2` at the hit point.
+pub(crate) fn intersect_triangle(
+    p0: &Point3,
+    p1: &Point3,
+    p2: &Point3,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = *p1 - *p0;
+    let edge2 = *p2 - *p0;
+    let p_vec = ray.direction().cross(&edge2);
+    let det = edge1.dot(&p_vec);
+
+    if det.abs() < 1e-12 {
+        // ray is parallel to the triangle
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let t_vec = ray.origin() - *p0;
+    let b1 = t_vec.dot(&p_vec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q_vec = t_vec.cross(&edge1);
+    let b2 = ray.direction().dot(&q_vec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q_vec) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+/// Bounding box of a triangle, padded so that axis-aligned triangles still have volume.
+pub(crate) fn triangle_bounding_box(p0: &Point3, p1: &Point3, p2: &Point3) -> Aabb {
+    let offset = 0.0001;
+    let mut min = Point3::default();
+    let mut max = Point3::default();
+
+    for a in 0..3 {
+        min[a] = p0[a].min(p1[a]).min(p2[a]) - offset;
+        max[a] = p0[a].max(p1[a]).max(p2[a]) + offset;
+    }
+
+    Aabb::new(min, max)
+}
+
+pub(crate) fn triangle_area(p0: &Point3, p1: &Point3, p2: &Point3) -> f64 {
+    0.5 * (*p1 - *p0).cross(&(*p2 - *p0)).length()
+}
+
+/// Maps two uniform samples in [0,1) to uniformly distributed barycentric coordinates.
+pub(crate) fn uniform_barycentric(r1: f64, r2: f64) -> (f64, f64, f64) {
+    let su0 = r1.sqrt();
+    let b1 = 1.0 - su0;
+    let b2 = r2 * su0;
+    (1.0 - b1 - b2, b1, b2)
+}
@@ -3,9 +3,9 @@ use crate::math::Ray;
 use crate::{
     hittable::{HitRecord, Hittable},
     math::{Aabb, Color, Vec3},
+    sampler::{hash, hash_to_float},
     texture::*,
 };
-use rand::{thread_rng, Rng};
 use std::sync::Arc;
 
 pub struct ConstantMedium {
@@ -71,7 +71,18 @@ impl Hittable for ConstantMedium {
 
         let ray_len = ray.direction().length();
         let distance_inside = (hit_1.t - hit_0.t) * ray_len;
-        let hit_dist = self.neg_inv_density * thread_rng().gen::<f64>().ln();
+        // `hit` has no sampler, so the scattering distance is chosen by hashing the ray. Rays
+        // come from the render's samples, so this stays reproducible for a given seed.
+        let u = hash_to_float(hash(&[
+            ray.origin().x().to_bits(),
+            ray.origin().y().to_bits(),
+            ray.origin().z().to_bits(),
+            ray.direction().x().to_bits(),
+            ray.direction().y().to_bits(),
+            ray.direction().z().to_bits(),
+            ray.time().to_bits(),
+        ]));
+        let hit_dist = self.neg_inv_density * (1.0 - u).ln();
 
         if hit_dist > distance_inside {
             return None;
@@ -81,6 +92,7 @@ impl Hittable for ConstantMedium {
         hit_rec.t = hit_0.t + hit_dist / ray_len;
         hit_rec.point = ray.at(hit_rec.t);
         hit_rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        hit_rec.geometric_normal = hit_rec.normal;
         hit_rec.front_face = true;
         hit_rec.material = Some(self.phase_fn.clone());
 
@@ -0,0 +1,85 @@
+use super::{hash, hash_to_float, mix_bits, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Prime bases for the Halton dimensions. Later dimensions fall back to random values.
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// The Halton sequence, using the radical inverse in the `n`th prime base for dimension `n`.
+///
+/// Each pixel and dimension randomly permutes the digits of its points (Owen scrambling), so
+/// neighbouring pixels don't share their error and the sparse first points of large bases are
+/// spread over the whole interval.
+#[derive(Clone)]
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (u64, u64),
+    index: u64,
+    dimension: u64,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u64, _samples: u64) {
+        self.pixel = (x as u64, y as u64);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        let h = hash(&[self.pixel.0, self.pixel.1, dimension, self.seed]);
+        match PRIMES.get(dimension as usize) {
+            Some(&base) => scrambled_radical_inverse(base, self.index, h),
+            None => hash_to_float(hash(&[h, self.index])),
+        }
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+
+    fn clone_sampler(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+/// Mirrors the digits of `index` in `base` around the radix point, permuting each digit by a
+/// hash of `seed` and the digits before it.
+fn scrambled_radical_inverse(base: u64, mut index: u64, seed: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_n = 1.0;
+    let mut value = 0.0;
+    // digits chosen so far, only used to seed the next digit's permutation
+    let mut prefix = 0u64;
+
+    // keep going past the last nonzero digit until the result is at full precision, since
+    // trailing zeros get permuted too
+    while inv_base_n > f64::EPSILON {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_seed = mix_bits(seed ^ prefix) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_seed) as u64;
+        prefix = prefix.wrapping_mul(base).wrapping_add(digit);
+        inv_base_n *= inv_base;
+        value += digit as f64 * inv_base_n;
+        index = next;
+    }
+
+    value.min(ONE_MINUS_EPSILON)
+}
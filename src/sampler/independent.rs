@@ -0,0 +1,40 @@
+use super::{hash, Sampler, ONE_MINUS_EPSILON};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+/// Uniform random samples with no correlation between them.
+///
+/// Each pixel sample gets its own random stream, so results don't depend on the order pixels
+/// are rendered in.
+#[derive(Clone)]
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u64, _samples: u64) {
+        self.rng = Pcg32::seed_from_u64(hash(&[x as u64, y as u64, index, self.seed]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen::<f64>().min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+
+    fn clone_sampler(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
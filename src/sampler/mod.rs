@@ -0,0 +1,109 @@
+//! Sample generators for the random decisions made while rendering a pixel.
+//!
+//! Every random number used for a camera ray and the path it starts (pixel jitter, lens
+//! position, shutter time, light and BSDF sampling) is drawn from a [`Sampler`] as the next
+//! "dimension" of the current pixel sample. Low-discrepancy samplers spread those dimensions
+//! more evenly than independent random numbers, so images converge with fewer samples.
+
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+pub use halton::*;
+pub use independent::*;
+pub use sobol::*;
+pub use stratified::*;
+
+/// Generates the sample values for each pixel sample, one dimension at a time.
+///
+/// A renderer calls [`Sampler::start_pixel_sample`] before tracing each camera ray, then draws
+/// every random value for that ray's path with [`Sampler::get_1d`] and [`Sampler::get_2d`].
+/// Values are in `[0, 1)`.
+pub trait Sampler: Send + Sync {
+    /// Starts sample `index` of the `samples` taken in pixel `(x, y)`, restarting at the first
+    /// dimension.
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u64, samples: u64);
+
+    fn get_1d(&mut self) -> f64;
+
+    fn get_2d(&mut self) -> (f64, f64);
+
+    /// Creates an independent sampler with the same settings, for use on another thread.
+    fn clone_sampler(&self) -> Box<dyn Sampler>;
+}
+
+/// Names accepted by [`sampler_by_name`].
+pub const SAMPLER_NAMES: &[&str] = &["independent", "stratified", "halton", "sobol"];
+
+/// Looks up a sampler by name, seeding it with `seed`.
+pub fn sampler_by_name(name: &str, seed: u64) -> Option<Box<dyn Sampler>> {
+    match name {
+        "independent" => Some(Box::new(IndependentSampler::new(seed))),
+        "stratified" => Some(Box::new(StratifiedSampler::new(seed))),
+        "halton" => Some(Box::new(HaltonSampler::new(seed))),
+        "sobol" => Some(Box::new(SobolSampler::new(seed))),
+        _ => None,
+    }
+}
+
+/// The largest `f64` below 1, so that samples never reach the end of their interval.
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Finalizer from SplitMix64, which spreads every input bit over the whole output.
+pub(crate) fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5_d329_728e_a185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81da_def4_bc2d_d44d);
+    v ^= v >> 33;
+    v
+}
+
+/// Hashes a list of values into a single well-mixed value.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e37_79b9_7f4a_7c15, |h, &v| {
+        mix_bits(h ^ v.wrapping_add(h << 6))
+    })
+}
+
+/// Converts the high bits of a hash to a float in `[0, 1)`.
+pub(crate) fn hash_to_float(h: u64) -> f64 {
+    (h >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Returns element `i` of a random permutation of `0..n` chosen by `seed`, without building
+/// the permutation (Kensler, "Correlated Multi-Jittered Sampling").
+pub(crate) fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < n {
+            return i.wrapping_add(seed) % n;
+        }
+    }
+}
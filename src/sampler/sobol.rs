@@ -0,0 +1,139 @@
+use super::{hash, Sampler, ONE_MINUS_EPSILON};
+
+/// Direction numbers for the first four Sobol dimensions.
+const DIRECTIONS: [[u32; 32]; 4] = sobol_directions();
+
+/// Owen-scrambled Sobol points, following Burley's "Practical Hash-based Owen Scrambling".
+///
+/// Dimensions are taken from 4D Sobol points. Each group of four dimensions uses its own
+/// scrambling seed and a nested uniform shuffle of the sample index, which decorrelates the
+/// groups from each other and from neighbouring pixels while keeping each group's
+/// stratification.
+#[derive(Clone)]
+pub struct SobolSampler {
+    seed: u64,
+    pixel: (u64, u64),
+    index: u32,
+    dimension: u64,
+    /// The current group of four dimensions, generated together.
+    point: [f64; 4],
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            dimension: 0,
+            point: [0.0; 4],
+        }
+    }
+
+    fn generate_point(&mut self) {
+        let group = self.dimension / 4;
+        let seed = hash(&[self.pixel.0, self.pixel.1, group, self.seed]) as u32;
+        let index = nested_uniform_scramble(self.index, seed);
+
+        for (d, value) in self.point.iter_mut().enumerate() {
+            let dimension_seed = hash(&[seed as u64, d as u64]) as u32;
+            let x = nested_uniform_scramble(sobol(index, d), dimension_seed);
+            *value = (x as f64 / 4_294_967_296.0).min(ONE_MINUS_EPSILON);
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u64, _samples: u64) {
+        self.pixel = (x as u64, y as u64);
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        if self.dimension.is_multiple_of(4) {
+            self.generate_point();
+        }
+        let value = self.point[(self.dimension % 4) as usize];
+        self.dimension += 1;
+        value
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        // keep both dimensions in the same group so they are stratified together
+        if self.dimension % 4 == 3 {
+            self.dimension += 1;
+        }
+        (self.get_1d(), self.get_1d())
+    }
+
+    fn clone_sampler(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
+
+fn sobol(index: u32, dimension: usize) -> u32 {
+    let mut x = 0;
+    for (bit, direction) in DIRECTIONS[dimension].iter().enumerate() {
+        if (index >> bit) & 1 != 0 {
+            x ^= direction;
+        }
+    }
+    x
+}
+
+/// Owen scrambling of all 32 bits of `x`, as a hash-based random permutation of each level.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+/// Builds the direction numbers from the primitive polynomials and initial values of Joe and
+/// Kuo for dimensions 2 to 4. The first dimension is the van der Corput sequence.
+const fn sobol_directions() -> [[u32; 32]; 4] {
+    // (degree, polynomial coefficients, initial direction integers)
+    const POLYNOMIALS: [(usize, u32, [u32; 3]); 3] =
+        [(1, 0, [1, 0, 0]), (2, 1, [1, 3, 0]), (3, 1, [1, 3, 1])];
+
+    let mut directions = [[0u32; 32]; 4];
+
+    let mut i = 0;
+    while i < 32 {
+        directions[0][i] = 1 << (31 - i);
+        i += 1;
+    }
+
+    let mut d = 0;
+    while d < 3 {
+        let (s, a, m) = POLYNOMIALS[d];
+        let v = &mut directions[d + 1];
+
+        let mut i = 0;
+        while i < 32 {
+            if i < s {
+                v[i] = m[i] << (31 - i);
+            } else {
+                v[i] = v[i - s] ^ (v[i - s] >> s);
+                let mut k = 1;
+                while k < s {
+                    if (a >> (s - 1 - k)) & 1 != 0 {
+                        v[i] ^= v[i - k];
+                    }
+                    k += 1;
+                }
+            }
+            i += 1;
+        }
+        d += 1;
+    }
+
+    directions
+}
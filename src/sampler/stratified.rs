@@ -0,0 +1,76 @@
+use super::{hash, hash_to_float, mix_bits, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Jittered samples, with each dimension split into one stratum per sample.
+///
+/// Sample `i` of a pixel is placed in a randomly permuted stratum for every dimension, and 2D
+/// samples use a grid of close to `sqrt(n) x sqrt(n)` cells. Strata are chosen by hashing
+/// rather than stored, so the sampler keeps no per-pixel state.
+#[derive(Clone)]
+pub struct StratifiedSampler {
+    seed: u64,
+    pixel: (u64, u64),
+    index: u64,
+    samples: u64,
+    dimension: u64,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: (0, 0),
+            index: 0,
+            samples: 1,
+            dimension: 0,
+        }
+    }
+
+    /// Picks this sample's stratum out of `strata`, and a hash for jittering inside it.
+    fn stratum(&mut self, strata: u64) -> (u64, u64) {
+        let dimension_hash = hash(&[self.pixel.0, self.pixel.1, self.dimension, self.seed]);
+        self.dimension += 1;
+
+        // each round of `strata` samples covers every stratum once
+        let round = self.index / strata;
+        let permutation_seed = hash(&[dimension_hash, round]) as u32;
+        let stratum = permutation_element(
+            (self.index % strata) as u32,
+            strata as u32,
+            permutation_seed,
+        );
+
+        (stratum as u64, hash(&[dimension_hash, self.index]))
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: u64, samples: u64) {
+        self.pixel = (x as u64, y as u64);
+        self.index = index;
+        self.samples = samples.clamp(1, u32::MAX as u64);
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let strata = self.samples;
+        let (stratum, jitter) = self.stratum(strata);
+        ((stratum as f64 + hash_to_float(jitter)) / strata as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let x_strata = (self.samples as f64).sqrt().floor().max(1.0) as u64;
+        let y_strata = self.samples.div_ceil(x_strata);
+        let (stratum, jitter) = self.stratum(x_strata * y_strata);
+
+        let dx = hash_to_float(jitter);
+        let dy = hash_to_float(mix_bits(jitter));
+        (
+            (((stratum % x_strata) as f64 + dx) / x_strata as f64).min(ONE_MINUS_EPSILON),
+            (((stratum / x_strata) as f64 + dy) / y_strata as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+
+    fn clone_sampler(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+}
@@ -2,144 +2,329 @@
 
 use crate::{
     camera::Camera,
-    hittable::{Hittable, HittableList},
-    material::ScatterRecord,
-    math::{Color, Ray, Vec3},
-    pdf::*,
+    film::{color_luminance, BoxFilter, Film, Filter},
+    hittable::HittableList,
+    integrator::{Integrator, PathIntegrator},
+    light::{ConstantEnvironment, Environment, Light, LightList},
+    math::Color,
+    sampler::{IndependentSampler, Sampler},
 };
 use indicatif::ProgressBar;
-use num::clamp;
-use rand::{thread_rng, Rng};
 use rayon::prelude::*;
-use std::sync::Arc;
 
-/// Stores a HittableList and extra scene data (like background)
+/// Stores a HittableList and extra scene data (like the environment)
 pub struct Scene {
     hittables: HittableList,
-    lights: Arc<dyn Hittable>,
-    background: Color,
+    lights: LightList,
+    light_sources: Vec<Box<dyn Light>>,
+    environment: Box<dyn Environment>,
     camera: Camera,
+    sampler: Box<dyn Sampler>,
+    filter: Box<dyn Filter>,
+    integrator: Box<dyn Integrator>,
 }
 
 impl Scene {
-    pub fn new(
-        hittables: HittableList,
-        lights: Arc<dyn Hittable>,
-        background: Color,
-        camera: Camera,
-    ) -> Self {
+    /// Creates a scene, finding the emissive surfaces among `hittables` to sample as lights.
+    pub fn new(hittables: HittableList, background: Color, camera: Camera) -> Self {
         Self {
+            lights: LightList::from_hittables(&hittables),
+            light_sources: Vec::new(),
             hittables,
-            lights,
-            background,
+            environment: Box::new(ConstantEnvironment::new(background)),
             camera,
+            sampler: Box::new(IndependentSampler::new(0)),
+            filter: Box::new(BoxFilter::default()),
+            integrator: Box::new(PathIntegrator::default()),
         }
     }
 
-    /// Render a [`Scene`](crate::hittable::Scene) to an [`Image`](crate::image::Image)
+    pub fn hittables(&self) -> &HittableList {
+        &self.hittables
+    }
+
+    /// The scene's emissive surfaces, if it has any.
+    pub fn lights(&self) -> Option<&LightList> {
+        if self.lights.is_empty() {
+            None
+        } else {
+            Some(&self.lights)
+        }
+    }
+
+    /// Adds a point, spot or directional light, which unlike emissive surfaces isn't part of
+    /// the scene's geometry.
+    pub fn add_light(&mut self, light: impl Light + 'static) {
+        self.light_sources.push(Box::new(light));
+    }
+
+    /// Lights added with [`Scene::add_light`].
+    pub fn light_sources(&self) -> &[Box<dyn Light>] {
+        &self.light_sources
+    }
+
+    /// Light arriving from beyond the scene, seen by rays that miss every object.
+    pub fn environment(&self) -> &dyn Environment {
+        self.environment.as_ref()
+    }
+
+    /// Replaces the constant background color the scene was created with.
+    pub fn set_environment(&mut self, environment: Box<dyn Environment>) {
+        self.environment = environment;
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// Sets the light transport algorithm used to compute each camera ray's color, replacing
+    /// the default [`PathIntegrator`].
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    /// Sets the sampler used for every random decision while rendering, replacing the default
+    /// [`IndependentSampler`].
+    pub fn set_sampler(&mut self, sampler: Box<dyn Sampler>) {
+        self.sampler = sampler;
+    }
+
+    /// Sets the filter samples are splatted onto the film with, replacing the default
+    /// half-pixel [`BoxFilter`].
+    pub fn set_filter(&mut self, filter: Box<dyn Filter>) {
+        self.filter = filter;
+    }
+
+    /// Renders the scene to a linear HDR [`Film`].
+    ///
+    /// The film holds unclamped radiance; use [`Film::to_rgb8`] or [`Film::save`] to produce an
+    /// 8-bit image.
+    ///
+    /// Every pixel sample starts its own stream from the scene's [`Sampler`], so a given
+    /// sampler and seed always produce the same film, however rayon schedules the pixels.
     pub fn render(
         &self,
         image_width: usize,
         image_height: usize,
         samples: u64,
         max_depth: u64,
-    ) -> Vec<u8> {
+    ) -> Film {
         let progress = ProgressBar::new(image_height as u64 * image_width as u64);
 
-        let channels: usize = 3;
-        let mut image = vec![0u8; image_width * image_height * channels];
-
-        image
-            .par_chunks_exact_mut(image_width * channels)
-            .rev()
-            .enumerate()
-            .for_each(|(j, row)| {
-                row.par_chunks_exact_mut(channels)
-                    .enumerate()
-                    .for_each(|(i, pixel)| {
-                        let mut rng = thread_rng();
-                        let mut sample_acc = Vec3::default();
-                        for _ in 0..samples {
-                            let u = (i as f64 + rng.gen::<f64>()) / (image_width as f64 - 1.0);
-                            let v = (j as f64 + rng.gen::<f64>()) / (image_height as f64 - 1.0);
-                            let ray = self.camera.get_ray(u, v);
-                            sample_acc += ray_color(ray, self, max_depth);
-                        }
-                        sample_acc /= samples as i32;
+        let mut film = Film::new(image_width, image_height);
+        let mut estimates = vec![PixelEstimate::default(); image_width * image_height];
+        let pixels: Vec<usize> = (0..estimates.len()).collect();
+        self.render_pass(
+            &mut film,
+            &mut estimates,
+            &pixels,
+            (samples, samples),
+            max_depth,
+            Some(&progress),
+        );
 
-                        // replace NaNs with zero
-                        let r = sample_acc.x();
-                        let g = sample_acc.y();
-                        let b = sample_acc.z();
+        progress.finish();
+        film
+    }
 
-                        if r != r {
-                            sample_acc[0] = 0.0;
-                        }
+    /// Renders the scene to a linear HDR [`Film`], spending more samples on noisier pixels.
+    ///
+    /// Every pixel first gets `options.min_samples` samples. Pixels are then sampled again in
+    /// passes of `options.pass_samples` until the estimated relative error of their mean
+    /// luminance drops below `options.threshold` or they reach `options.max_samples`. The
+    /// film's sample counts record how many samples each pixel took; see
+    /// [`Film::sample_heatmap`].
+    pub fn render_adaptive(
+        &self,
+        image_width: usize,
+        image_height: usize,
+        options: AdaptiveOptions,
+        max_depth: u64,
+    ) -> Film {
+        let progress = ProgressBar::new(image_height as u64 * image_width as u64);
 
-                        if g != g {
-                            sample_acc[1] = 0.0;
-                        }
+        // at least two samples are needed to estimate the variance
+        let max_samples = options.max_samples.max(1);
+        let min_samples = options.min_samples.max(2).min(max_samples);
 
-                        if b != b {
-                            sample_acc[2] = 0.0;
-                        }
+        let mut film = Film::new(image_width, image_height);
+        let mut estimates = vec![PixelEstimate::default(); image_width * image_height];
+        let mut active: Vec<usize> = (0..estimates.len()).collect();
+        let mut pass_samples = min_samples;
 
-                        // gamma correct
-                        let r = (255.0 * clamp(sample_acc[0].sqrt(), 0., 0.999)) as u8;
-                        let g = (255.0 * clamp(sample_acc[1].sqrt(), 0., 0.999)) as u8;
-                        let b = (255.0 * clamp(sample_acc[2].sqrt(), 0., 0.999)) as u8;
+        while !active.is_empty() {
+            self.render_pass(
+                &mut film,
+                &mut estimates,
+                &active,
+                (pass_samples, max_samples),
+                max_depth,
+                None,
+            );
 
-                        pixel[0] = r;
-                        pixel[1] = g;
-                        pixel[2] = b;
-                        progress.inc(1);
-                    });
+            active.retain(|&index| {
+                let estimate = &estimates[index];
+                let done = estimate.samples >= max_samples
+                    || estimate.relative_error() < options.threshold;
+                if done {
+                    progress.inc(1);
+                }
+                !done
             });
+            pass_samples = options.pass_samples.max(1);
+        }
 
         progress.finish();
-        image
+        film
     }
-}
 
-fn ray_color(ray: Ray, scene: &Scene, depth: u64) -> Vec3 {
-    if depth <= 0 {
-        return Vec3::default();
-    }
+    /// Takes up to `count` more samples in each of `pixels`, stopping at `total` samples per
+    /// pixel, and splats them onto `film`. `pixels` are film indices in increasing order.
+    ///
+    /// Bands of rows are rendered in parallel into their own films, which also cover the rows
+    /// the filter reaches above and below the band. They are merged in order afterwards, so the
+    /// result doesn't depend on how the bands were scheduled.
+    fn render_pass(
+        &self,
+        film: &mut Film,
+        estimates: &mut [PixelEstimate],
+        pixels: &[usize],
+        (count, total): (u64, u64),
+        max_depth: u64,
+        progress: Option<&ProgressBar>,
+    ) {
+        let (image_width, image_height) = (film.width(), film.height());
+        let margin = self.filter.radius().ceil() as usize + 1;
+        let band_of = |index: &usize| index / image_width / BAND_HEIGHT;
 
-    if let Some(hit_rec) = scene.hittables.hit(&ray, 0.001, f64::INFINITY) {
-        if let Some(ref material) = hit_rec.material {
-            let mut scatter_rec = ScatterRecord::new();
-            let emitted = material.emitted(&ray, &hit_rec, hit_rec.u, hit_rec.v, &hit_rec.point);
+        let previous: &[PixelEstimate] = estimates;
+        let bands: Vec<(isize, Film, Vec<PixelEstimate>)> = pixels
+            .chunk_by(|a, b| band_of(a) == band_of(b))
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|band| {
+                let top = (band_of(&band[0]) * BAND_HEIGHT) as isize - margin as isize;
+                let mut band_film = Film::new(image_width, BAND_HEIGHT + 2 * margin);
 
-            if !material.scatter(&ray, &hit_rec, &mut scatter_rec) {
-                return emitted;
-            }
+                let updated = band
+                    .iter()
+                    .map(|&index| {
+                        let (x, y) = (index % image_width, index / image_width);
+                        let mut estimate = previous[index];
+                        let mut sampler = self.sampler.clone_sampler();
 
-            if let Some(specular_ray) = scatter_rec.specular_ray {
-                return scatter_rec.attenuation * ray_color(specular_ray, scene, depth - 1);
-            }
+                        let first = estimate.samples;
+                        let last = total.min(first + count);
+                        for sample_index in first..last {
+                            sampler.start_pixel_sample(x, y, sample_index, total);
+                            let (dx, dy) = sampler.get_2d();
+                            let (film_x, film_y) = (x as f64 + dx, y as f64 + dy);
+                            let color = self.sample(
+                                (film_x, film_y),
+                                (image_width, image_height),
+                                max_depth,
+                                sampler.as_mut(),
+                            );
+                            estimate.add(color);
+                            band_film.splat(
+                                (film_x, film_y - top as f64),
+                                color,
+                                self.filter.as_ref(),
+                            );
+                        }
+
+                        if let Some(progress) = progress {
+                            progress.inc(1);
+                        }
+                        estimate
+                    })
+                    .collect();
+                (top, band_film, updated)
+            })
+            .collect();
 
-            let mixture_pdf: MixturePdf;
-            {
-                let light_pdf = HittablePdf::new(scene.lights.clone(), hit_rec.point);
-                mixture_pdf =
-                    MixturePdf::new(Arc::new(light_pdf), scatter_rec.pdf.unwrap().clone());
+        for ((top, band_film, updated), band) in bands
+            .into_iter()
+            .zip(pixels.chunk_by(|a, b| band_of(a) == band_of(b)))
+        {
+            film.merge(&band_film, 0, top);
+            for (&index, estimate) in band.iter().zip(updated) {
+                estimates[index] = estimate;
             }
+        }
+    }
+
+    /// Traces a camera ray through film position `(film_x, film_y)` and returns its radiance.
+    fn sample(
+        &self,
+        (film_x, film_y): (f64, f64),
+        (image_width, image_height): (usize, usize),
+        max_depth: u64,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        // film y runs down the image, camera v up it
+        let u = film_x / (image_width as f64 - 1.0);
+        let v = (image_height as f64 - film_y) / (image_height as f64 - 1.0);
+        let ray = self.camera.get_ray(u, v, sampler);
+        self.integrator.radiance(self, ray, max_depth, sampler)
+    }
+}
 
-            let scattered = Ray::new(hit_rec.point, mixture_pdf.generate(), ray.time());
-            let pdf_val = mixture_pdf.value(&scattered.direction());
+/// Number of film rows rendered together by one task, sharing a film for splatting.
+const BAND_HEIGHT: usize = 8;
 
-            return emitted
-                + scatter_rec.attenuation
-                    * material.scattering_pdf(&ray, &hit_rec, &scattered)
-                    * ray_color(scattered, scene, depth - 1)
-                    / pdf_val;
+/// Settings for [`Scene::render_adaptive`].
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveOptions {
+    /// Samples taken in every pixel before its error is first estimated. At least 2.
+    pub min_samples: u64,
+    /// Largest number of samples taken in any pixel.
+    pub max_samples: u64,
+    /// Samples added to each unconverged pixel per pass.
+    pub pass_samples: u64,
+    /// Relative standard error of a pixel's mean luminance below which it is no longer sampled.
+    pub threshold: f64,
+}
+
+impl Default for AdaptiveOptions {
+    fn default() -> Self {
+        Self {
+            min_samples: 16,
+            max_samples: 1024,
+            pass_samples: 16,
+            threshold: 0.05,
         }
+    }
+}
+
+/// Luminance below which errors are measured relative to this value instead, so that nearly
+/// black pixels don't need an absolute error close to zero.
+const MIN_ERROR_LUMINANCE: f64 = 0.01;
+
+/// Running luminance sums of a pixel's own samples, for estimating its error.
+#[derive(Debug, Clone, Copy, Default)]
+struct PixelEstimate {
+    luminance_sum: f64,
+    luminance_squared_sum: f64,
+    samples: u64,
+}
 
-        // no material - use red to stand out
-        Color::new(1.0, 0.0, 0.0)
-    } else {
-        // no hit
-        scene.background
+impl PixelEstimate {
+    fn add(&mut self, color: Color) {
+        let y = color_luminance(color);
+        self.luminance_sum += y;
+        self.luminance_squared_sum += y * y;
+        self.samples += 1;
+    }
+
+    /// Standard error of the mean luminance, relative to the mean.
+    fn relative_error(&self) -> f64 {
+        if self.samples < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.samples as f64;
+        let mean = self.luminance_sum / n;
+        let variance = (self.luminance_squared_sum - n * mean * mean).max(0.0) / (n - 1.0);
+        (variance / n).sqrt() / mean.max(MIN_ERROR_LUMINANCE)
     }
 }
@@ -1,7 +1,8 @@
 use super::Texture;
 use crate::math::{Color, Point3};
-use image::open;
+use image::{open, ImageResult};
 use num::clamp;
+use std::path::Path;
 
 const CHANNELS: usize = 3;
 const SCALE: f64 = 1.0 / 255.0;
@@ -15,13 +16,18 @@ pub struct Image {
 
 impl Image {
     pub fn new(filename: &str) -> Self {
-        let img_rgb8 = open(filename).unwrap().as_rgb8().unwrap().to_owned();
-        Self {
+        Self::open(filename).unwrap()
+    }
+
+    /// Loads an image file, converting it to 8-bit RGB if it is stored in another format.
+    pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        let img_rgb8 = open(path)?.to_rgb();
+        Ok(Self {
             bytes_per_scanline: CHANNELS * img_rgb8.width() as usize,
             width: img_rgb8.width(),
             height: img_rgb8.height(),
             data: img_rgb8.into_raw(),
-        }
+        })
     }
 }
 
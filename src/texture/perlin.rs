@@ -1,6 +1,7 @@
 use super::Texture;
 use crate::math::{Color, Point3, Vec3};
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
 
 const POINT_COUNT: usize = 256;
 const N: i32 = POINT_COUNT as i32 - 1;
@@ -12,8 +13,14 @@ pub struct PerlinNoise {
 
 impl PerlinNoise {
     pub fn new(scale: f64) -> Self {
+        Self::with_seed(scale, 0)
+    }
+
+    /// Creates noise whose random gradients and permutations are generated from `seed`, so the
+    /// same seed always gives the same pattern.
+    pub fn with_seed(scale: f64, seed: u64) -> Self {
         Self {
-            perlin: Perlin::new(),
+            perlin: Perlin::new(&mut Pcg32::seed_from_u64(seed)),
             scale,
         }
     }
@@ -36,18 +43,18 @@ struct Perlin {
 }
 
 impl Perlin {
-    pub fn new() -> Self {
+    pub fn new<R: Rng>(rng: &mut R) -> Self {
         let mut ranvec = [Vec3::default(); POINT_COUNT];
         ranvec
             .iter_mut()
-            .for_each(|f| *f = Vec3::random_in_range(-1.0, 1.0).unit());
+            .for_each(|f| *f = Vec3::random_in_range_with(rng, -1.0, 1.0).unit());
         let mut perm_x = [0; POINT_COUNT];
         let mut perm_y = [0; POINT_COUNT];
         let mut perm_z = [0; POINT_COUNT];
 
-        Self::perlin_generate_perm(&mut perm_x);
-        Self::perlin_generate_perm(&mut perm_y);
-        Self::perlin_generate_perm(&mut perm_z);
+        Self::perlin_generate_perm(&mut perm_x, rng);
+        Self::perlin_generate_perm(&mut perm_y, rng);
+        Self::perlin_generate_perm(&mut perm_z, rng);
 
         Self {
             ranvec,
@@ -116,17 +123,17 @@ impl Perlin {
         accum
     }
 
-    fn perlin_generate_perm(buf: &mut [i32]) {
+    fn perlin_generate_perm<R: Rng>(buf: &mut [i32], rng: &mut R) {
         for i in 0..buf.len() {
             buf[i] = i as i32;
         }
 
-        Self::permute(buf);
+        Self::permute(buf, rng);
     }
 
-    fn permute(buf: &mut [i32]) {
+    fn permute<R: Rng>(buf: &mut [i32], rng: &mut R) {
         for i in (1..buf.len()).rev() {
-            let target = thread_rng().gen_range(0, i);
+            let target = rng.gen_range(0, i);
             buf.swap(i, target);
         }
     }